@@ -0,0 +1,570 @@
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+use crate::backup::{apply_retention, snapshot_account};
+use crate::i18n::tr;
+use crate::paths::live_auth_path;
+use crate::projects::rename_in_projects;
+use crate::secrets::{forget, read_plaintext, rename_secret};
+use crate::session::{self, SessionPolicy, SwitchOutcome};
+use crate::settings::{load_settings, rename_in_settings, save_settings};
+use crate::{
+    codex_root, current_state, load_state, update_tray, with_watcher_paused, AccountSummary,
+};
+
+/// Longest account name we accept; keeps directory names sane on every filesystem.
+const MAX_NAME_LEN: usize = 64;
+
+/// Checks an account name against the vault naming policy.
+///
+/// Names become directory names under `accounts/`, so anything that could escape
+/// that directory (separators, `..`, a leading dot) is rejected outright.
+pub fn validate_account_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err(tr!("error.name_empty"));
+    }
+    if name.len() > MAX_NAME_LEN {
+        return Err(tr!("error.name_too_long", name = name, max = MAX_NAME_LEN));
+    }
+    if name.starts_with('.') {
+        return Err(tr!("error.name_leading_dot", name = name));
+    }
+    if name.contains("..") || name.contains('/') || name.contains('\\') {
+        return Err(tr!("error.name_separator", name = name));
+    }
+    if let Some(c) = name
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '@' | '+')))
+    {
+        return Err(tr!("error.name_invalid_char", name = name, char = c));
+    }
+    Ok(())
+}
+
+pub fn accounts_dir() -> PathBuf {
+    codex_root().join("accounts")
+}
+
+pub fn account_dir(name: &str) -> PathBuf {
+    accounts_dir().join(name)
+}
+
+/// Writes `contents` via a temp file + rename, restricted to the current user.
+pub fn write_private(path: &Path, contents: &[u8]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.to_string_lossy(), e))?;
+    }
+    let tmp = path.with_extension("tmp");
+    let write = || -> std::io::Result<()> {
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(contents)?;
+        // Flush to disk before the rename so a crash can't leave an empty file.
+        file.sync_all()
+    };
+    write().map_err(|e| format!("Failed to write {}: {}", tmp.to_string_lossy(), e))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = fs::set_permissions(&tmp, fs::Permissions::from_mode(0o600));
+    }
+
+    fs::rename(&tmp, path).map_err(|e| {
+        let _ = fs::remove_file(&tmp);
+        format!("Failed to replace {}: {}", path.to_string_lossy(), e)
+    })
+}
+
+/// Converts a vault account record into the shape the Codex CLI expects,
+/// mirroring `ConfigManager.sync_legacy_auth` on the Python side.
+fn live_auth_payload(account: &serde_json::Value) -> serde_json::Value {
+    let mut data = serde_json::Map::new();
+    if let Some(key) = account["api_key"].as_str() {
+        data.insert("api_key".into(), key.into());
+        data.insert("OPENAI_API_KEY".into(), key.into());
+    }
+    if let Some(email) = account["email"].as_str() {
+        data.insert("email".into(), email.into());
+    }
+    if let Some(tokens) = account["tokens"].as_object() {
+        data.insert("tokens".into(), serde_json::Value::Object(tokens.clone()));
+        for (k, v) in tokens {
+            data.insert(k.clone(), v.clone());
+        }
+    }
+    data.insert(
+        "last_refresh".into(),
+        chrono::Utc::now()
+            .format("%Y-%m-%dT%H:%M:%SZ")
+            .to_string()
+            .into(),
+    );
+    serde_json::Value::Object(data)
+}
+
+/// Copies an account's credentials into the live Codex auth file.
+///
+/// Only plaintext (`auth.json`) accounts can be applied; `auth.enc` needs the
+/// CLI's master key, which the tray never holds.
+pub fn apply_credentials(name: &str) -> Result<(), String> {
+    validate_account_name(name)?;
+    let dir = account_dir(name);
+    let Some(content) =
+        read_plaintext(name).map_err(|e| format!("Failed to read credentials: {}", e))?
+    else {
+        return Err(if dir.join("auth.enc").exists() {
+            tr!("error.account_encrypted", name = name)
+        } else {
+            tr!("error.account_no_credentials", name = name)
+        });
+    };
+    let account: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|e| tr!("error.credentials_corrupt", name = name, error = e))?;
+    let payload = serde_json::to_vec_pretty(&live_auth_payload(&account))
+        .map_err(|e| format!("Failed to serialize credentials: {}", e))?;
+
+    write_private(&live_auth_path(), &payload)
+}
+
+/// Points `config.json`'s `active_account` at `name`, keeping every other key.
+pub fn write_active_account(name: &str) -> Result<(), String> {
+    set_config_active(name.into())
+}
+
+/// Sets `config.json`'s `active_account` to null: no vault account is live.
+pub fn clear_active_account() -> Result<(), String> {
+    set_config_active(serde_json::Value::Null)
+}
+
+fn set_config_active(value: serde_json::Value) -> Result<(), String> {
+    let path = codex_root().join("config.json");
+    let mut config = match fs::read_to_string(&path) {
+        Ok(content) => serde_json::from_str::<serde_json::Value>(&content)
+            .map_err(|e| format!("config.json is corrupt: {}", e))?,
+        Err(_) => serde_json::json!({}),
+    };
+    if !config.is_object() {
+        return Err("config.json is not a JSON object".to_string());
+    }
+    config["active_account"] = value;
+
+    let body = serde_json::to_vec_pretty(&config)
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+    write_private(&path, &body)
+}
+
+/// Runs the bundled `codex-backend` CLI (the same sidecar the frontend uses).
+pub fn run_cli(args: &[&str]) -> Result<String, String> {
+    let exe = env::current_exe().map_err(|e| format!("Cannot locate app binary: {}", e))?;
+    let sidecar = exe.with_file_name(if cfg!(windows) {
+        "codex-backend.exe"
+    } else {
+        "codex-backend"
+    });
+    let output = std::process::Command::new(&sidecar)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run codex-backend: {}", e))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+/// The step of a switch that failed.
+#[derive(serde::Serialize, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum SwitchStage {
+    Validate,
+    /// A Codex session is running; retry with `force` or `when_idle`.
+    Session,
+    Backup,
+    Credentials,
+    Config,
+}
+
+/// Why a switch failed, and whether the files it touched were put back.
+#[derive(serde::Serialize, Debug)]
+pub struct SwitchError {
+    pub stage: SwitchStage,
+    pub message: String,
+    pub rolled_back: bool,
+}
+
+impl SwitchError {
+    pub fn new(stage: SwitchStage, message: String) -> Self {
+        Self {
+            stage,
+            message,
+            rolled_back: false,
+        }
+    }
+}
+
+impl fmt::Display for SwitchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        if self.rolled_back {
+            write!(f, " (changes rolled back)")?;
+        }
+        Ok(())
+    }
+}
+
+impl From<SwitchError> for String {
+    fn from(e: SwitchError) -> Self {
+        e.to_string()
+    }
+}
+
+/// Puts `path` back to `before`: rewritten if it existed, removed if it didn't.
+fn restore_file(path: &Path, before: &Option<Vec<u8>>) -> Result<(), String> {
+    match before {
+        Some(bytes) => write_private(path, bytes),
+        None => match fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(format!(
+                "Failed to remove {}: {}",
+                path.to_string_lossy(),
+                e
+            )),
+            _ => Ok(()),
+        },
+    }
+}
+
+/// Makes `name` the active account: live credentials first, then `config.json`.
+///
+/// The outgoing account is snapshotted first and returned on success. If
+/// either write fails, both files are restored to their pre-switch contents.
+/// Encrypted accounts are handed to the CLI, which holds the master key.
+pub fn switch_to(name: &str) -> Result<Option<String>, SwitchError> {
+    let validate = || -> Result<(), String> {
+        validate_account_name(name)?;
+        if !account_dir(name).is_dir() {
+            return Err(tr!("error.account_not_found", name = name));
+        }
+        Ok(())
+    };
+    validate().map_err(|e| SwitchError::new(SwitchStage::Validate, e))?;
+
+    let mut backed_up = None;
+    if let Some(previous) = load_state().active_account.filter(|p| p != name) {
+        if account_dir(&previous).is_dir() {
+            snapshot_account(&previous).map_err(|e| {
+                SwitchError::new(
+                    SwitchStage::Backup,
+                    tr!("error.pre_switch_backup", name = previous, error = e),
+                )
+            })?;
+            apply_retention(&previous);
+            backed_up = Some(previous);
+        }
+    }
+
+    let dir = account_dir(name);
+    if !dir.join("auth.json").exists() && dir.join("auth.enc").exists() {
+        run_cli(&["switch", name]).map_err(|e| SwitchError::new(SwitchStage::Credentials, e))?;
+        apply_retention(name);
+        log::info!("Switched to '{}' via the CLI", name);
+        return Ok(backed_up);
+    }
+
+    let live_path = live_auth_path();
+    let config_path = codex_root().join("config.json");
+    let live_before = fs::read(&live_path).ok();
+    let config_before = fs::read(&config_path).ok();
+
+    let result = apply_credentials(name)
+        .map_err(|e| SwitchError::new(SwitchStage::Credentials, e))
+        .and_then(|()| {
+            write_active_account(name).map_err(|e| SwitchError::new(SwitchStage::Config, e))
+        });
+    if let Err(mut e) = result {
+        e.rolled_back = restore_file(&live_path, &live_before)
+            .and_then(|()| restore_file(&config_path, &config_before))
+            .inspect_err(|re| {
+                log::error!("Rollback after failed switch to '{}' failed: {}", name, re)
+            })
+            .is_ok();
+        return Err(e);
+    }
+
+    apply_retention(name);
+    log::info!("Switched to '{}'", name);
+    Ok(backed_up)
+}
+
+/// Switches the active account and refreshes the tray.
+///
+/// While a Codex session is running (see `session::active_session`) the
+/// switch is refused with a `session` error and a `codex-running` event,
+/// unless `force` is set, or queued until the session ends with `when_idle`.
+#[tauri::command]
+pub fn switch_account(
+    app: AppHandle,
+    name: String,
+    force: Option<bool>,
+    when_idle: Option<bool>,
+) -> Result<SwitchOutcome, SwitchError> {
+    let policy = if force.unwrap_or(false) {
+        SessionPolicy::Force
+    } else if when_idle.unwrap_or(false) {
+        SessionPolicy::Queue
+    } else {
+        SessionPolicy::Refuse
+    };
+    let outcome = session::switch(&app, &name, policy)?;
+    if outcome == SwitchOutcome::Switched {
+        let _ = update_tray(&app);
+    }
+    Ok(outcome)
+}
+
+pub fn ensure_exists(name: &str) -> Result<(), String> {
+    validate_account_name(name)?;
+    if !account_dir(name).is_dir() {
+        return Err(tr!("error.account_not_found", name = name));
+    }
+    Ok(())
+}
+
+/// Account names in tray order.
+#[tauri::command]
+pub fn list_accounts(app: AppHandle) -> Vec<String> {
+    current_state(&app).accounts
+}
+
+/// Creates `name` from a credential file (`auth.json` or `auth.enc`).
+#[tauri::command]
+pub fn add_account(app: AppHandle, name: String, source: String) -> Result<(), String> {
+    create_account_from_file(&name, Path::new(&source), None)?;
+    let _ = update_tray(&app);
+    Ok(())
+}
+
+/// Deletes an account's directory. Removing the active account leaves no
+/// account active; the live Codex credentials are not touched.
+#[tauri::command]
+pub fn remove_account(app: AppHandle, name: String) -> Result<(), String> {
+    ensure_exists(&name)?;
+    fs::remove_dir_all(account_dir(&name))
+        .map_err(|e| tr!("error.account_remove_failed", name = name, error = e))?;
+    forget(&name);
+    if load_state().active_account.as_deref() == Some(name.as_str()) {
+        clear_active_account()?;
+    }
+    let _ = update_tray(&app);
+    Ok(())
+}
+
+/// Renames an account, carrying over the active marker and any settings
+/// (labels, groups, rotation order) or project mappings that refer to it.
+#[tauri::command]
+pub fn rename_account(app: AppHandle, old_name: String, new_name: String) -> Result<(), String> {
+    ensure_exists(&old_name)?;
+    validate_account_name(&new_name)?;
+    if account_dir(&new_name).exists() {
+        return Err(tr!("error.account_exists", name = new_name));
+    }
+
+    fs::rename(account_dir(&old_name), account_dir(&new_name))
+        .map_err(|e| tr!("error.account_rename_failed", name = old_name, error = e))?;
+    rename_secret(&old_name, &new_name)?;
+    if load_state().active_account.as_deref() == Some(old_name.as_str()) {
+        write_active_account(&new_name)?;
+    }
+    let mut settings = load_settings();
+    rename_in_settings(&mut settings, &old_name, &new_name);
+    save_settings(&settings)?;
+    rename_in_projects(&old_name, &new_name)?;
+
+    let _ = update_tray(&app);
+    Ok(())
+}
+
+/// Cached usage for one account, as shown in the tray.
+#[tauri::command]
+pub fn get_usage(app: AppHandle, name: String) -> Result<AccountSummary, String> {
+    validate_account_name(&name)?;
+    Ok(AccountSummary::new(&current_state(&app), &name))
+}
+
+/// Name the vault expects for a credential file: encrypted blobs stay `auth.enc`,
+/// everything else is stored as the legacy plaintext `auth.json`.
+fn credential_file_name(source: &Path) -> &'static str {
+    match source.extension().and_then(|e| e.to_str()) {
+        Some("enc") => "auth.enc",
+        _ => "auth.json",
+    }
+}
+
+/// Creates `accounts/<name>/` and copies the credential file into it.
+///
+/// Fails if the account already exists so an import never clobbers live credentials.
+fn create_account_from_file(name: &str, source: &Path, note: Option<&str>) -> Result<(), String> {
+    validate_account_name(name)?;
+
+    if !source.is_file() {
+        return Err(tr!(
+            "error.credential_file_missing",
+            path = source.to_string_lossy()
+        ));
+    }
+
+    let dir = account_dir(name);
+    if dir.exists() {
+        return Err(tr!("error.account_exists", name = name));
+    }
+
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create account dir: {}", e))?;
+
+    let write_files = || -> Result<(), String> {
+        fs::copy(source, dir.join(credential_file_name(source)))
+            .map_err(|e| format!("Failed to copy credentials: {}", e))?;
+        if let Some(note) = note.filter(|n| !n.is_empty()) {
+            let meta = serde_json::json!({ "notes": note });
+            fs::write(dir.join("meta.json"), meta.to_string())
+                .map_err(|e| format!("Failed to write account note: {}", e))?;
+        }
+        Ok(())
+    };
+
+    // Don't leave a half-created account behind if the copy fails.
+    write_files().inspect_err(|_| {
+        let _ = fs::remove_dir_all(&dir);
+    })
+}
+
+#[derive(serde::Serialize)]
+pub struct ImportRow {
+    line: usize,
+    name: String,
+    ok: bool,
+    error: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+pub struct ImportReport {
+    imported: usize,
+    failed: usize,
+    rows: Vec<ImportRow>,
+}
+
+/// Bulk-creates accounts from a `name,credential_path[,note]` CSV.
+///
+/// A header row is skipped if its first column is literally `name`. Each row is
+/// processed independently: a bad row is reported and the import carries on.
+#[tauri::command]
+pub fn import_accounts_csv(app: AppHandle, path: String) -> Result<ImportReport, String> {
+    with_watcher_paused(&app, || import_csv_rows(&path))
+}
+
+fn import_csv_rows(path: &str) -> Result<ImportReport, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .comment(Some(b'#'))
+        .from_path(path)
+        .map_err(|e| format!("Failed to open CSV '{}': {}", path, e))?;
+
+    // Relative credential paths are resolved against the CSV's own directory.
+    let base_dir = Path::new(path)
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+
+    let mut report = ImportReport {
+        imported: 0,
+        failed: 0,
+        rows: Vec::new(),
+    };
+
+    for (idx, record) in reader.records().enumerate() {
+        // The file's own line number, counting the header and comment lines.
+        let position = match &record {
+            Ok(r) => r.position(),
+            Err(e) => e.position(),
+        };
+        let line = position.map_or(idx + 1, |p| p.line() as usize);
+        let record = match record {
+            Ok(r) => r,
+            Err(e) => {
+                report.failed += 1;
+                report.rows.push(ImportRow {
+                    line,
+                    name: String::new(),
+                    ok: false,
+                    error: Some(format!("Malformed row: {}", e)),
+                });
+                continue;
+            }
+        };
+
+        let name = record.get(0).unwrap_or_default().to_string();
+        if idx == 0 && name.eq_ignore_ascii_case("name") {
+            continue;
+        }
+        if name.is_empty() && record.iter().all(str::is_empty) {
+            continue;
+        }
+
+        let result = match record.get(1).filter(|p| !p.is_empty()) {
+            Some(cred) => {
+                let source = base_dir.join(cred);
+                create_account_from_file(&name, &source, record.get(2))
+            }
+            None => Err("Missing credential_path column".to_string()),
+        };
+
+        match result {
+            Ok(()) => {
+                report.imported += 1;
+                report.rows.push(ImportRow {
+                    line,
+                    name,
+                    ok: true,
+                    error: None,
+                });
+            }
+            Err(e) => {
+                report.failed += 1;
+                report.rows.push(ImportRow {
+                    line,
+                    name,
+                    ok: false,
+                    error: Some(e),
+                });
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_rows_report_their_file_line() {
+        let dir = std::env::temp_dir().join(format!("accounts-csv-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let csv = dir.join("accounts.csv");
+        // Neither credential file exists, so both rows fail without touching the vault.
+        fs::write(&csv, "name,credential_path\nwork,work.json\nhome\n").unwrap();
+
+        let report = import_csv_rows(&csv.to_string_lossy()).unwrap();
+        let lines: Vec<usize> = report.rows.iter().map(|r| r.line).collect();
+        assert_eq!(lines, [2, 3]);
+        assert_eq!(report.rows[1].name, "home");
+        assert!(!report.rows[1].ok);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::accounts::{SwitchError, SwitchStage};
+use crate::i18n::{limit_name, tr};
+use crate::settings::load_settings;
+use crate::{account_usage, current_state, notify, AppState};
+
+/// Last-seen (5h, weekly) percentages per account, so a notification fires
+/// once per upward crossing rather than on every cache rewrite.
+#[derive(Default)]
+pub struct UsageAlerts(Mutex<HashMap<String, (f64, f64)>>);
+
+fn current_usage(state: &AppState) -> HashMap<String, (f64, f64)> {
+    state
+        .accounts
+        .iter()
+        .filter_map(|name| Some((name.clone(), account_usage(state, name)?)))
+        .collect()
+}
+
+/// Records the usage seen at launch without notifying about it.
+pub fn seed<R: Runtime>(app: &AppHandle<R>) {
+    if let Some(alerts) = app.try_state::<UsageAlerts>() {
+        if let Ok(mut seen) = alerts.0.lock() {
+            *seen = current_usage(&current_state(app));
+        }
+    }
+}
+
+/// Notifies for every configured threshold a limit crossed upward since the
+/// last call, and when the active account's usage drops (usage only falls
+/// when a window resets). Accounts seen for the first time only set a baseline.
+pub fn check<R: Runtime>(app: &AppHandle<R>, state: &AppState) {
+    let Some(alerts) = app.try_state::<UsageAlerts>() else {
+        return;
+    };
+    let Ok(mut seen) = alerts.0.lock() else {
+        return;
+    };
+
+    let prefs = load_settings().notifications;
+    let current = current_usage(state);
+    for (name, &(p5, pw)) in &current {
+        let Some(&(old5, oldw)) = seen.get(name) else {
+            continue;
+        };
+        let is_active = state.active_account.as_deref() == Some(name.as_str());
+        for (limit, old, new) in [("5h", old5, p5), ("weekly", oldw, pw)] {
+            // Only the highest threshold crossed in one update is reported.
+            let crossed = prefs
+                .thresholds
+                .iter()
+                .copied()
+                .filter(|&t| old < t && new >= t)
+                .reduce(f64::max);
+            if let Some(threshold) = crossed.filter(|_| prefs.usage_thresholds) {
+                notify(
+                    app,
+                    &tr!("notify.usage_approaching.title"),
+                    &tr!(
+                        "notify.usage_approaching.body",
+                        name = name,
+                        threshold = format!("{:.0}", threshold),
+                        limit = limit_name(limit),
+                        usage = format!("{:.0}", new),
+                    ),
+                );
+            }
+            if prefs.window_resets && is_active && new < old {
+                notify(
+                    app,
+                    &tr!("notify.window_reset.title"),
+                    &tr!(
+                        "notify.window_reset.body_usage",
+                        name = name,
+                        limit = limit_name(limit),
+                        usage = format!("{:.0}", new),
+                    ),
+                );
+            }
+        }
+    }
+    *seen = current;
+}
+
+/// Reports the automatic snapshot a switch takes of the outgoing account.
+/// `result` is what `switch_to` returned; other failures aren't reported here.
+pub fn auto_backup<R: Runtime>(app: &AppHandle<R>, result: &Result<Option<String>, SwitchError>) {
+    let prefs = load_settings().notifications;
+    match result {
+        Ok(Some(account)) if prefs.backup_success => notify(
+            app,
+            &tr!("notify.backup_created.title"),
+            &tr!("notify.backup_created.before_switch", name = account),
+        ),
+        Err(e) if prefs.backup_failure && matches!(e.stage, SwitchStage::Backup) => {
+            notify(app, &tr!("notify.backup_failed.title"), &e.message)
+        }
+        _ => {}
+    }
+}
+
+/// Reports a scheduled vault backup; `Ok(None)` (nothing changed) is silent.
+pub fn vault_backup<R: Runtime>(app: &AppHandle<R>, result: &Result<Option<String>, String>) {
+    let prefs = load_settings().notifications;
+    match result {
+        Ok(Some(_)) if prefs.backup_success => notify(
+            app,
+            &tr!("notify.backup_created.title"),
+            &tr!("notify.backup_created.scheduled"),
+        ),
+        Err(e) if prefs.backup_failure => notify(app, &tr!("notify.backup_failed.title"), e),
+        _ => {}
+    }
+}
@@ -0,0 +1,279 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::{self, Read, Seek, Write};
+use std::path::{Component, Path};
+use tauri::AppHandle;
+
+use crate::accounts::{accounts_dir, validate_account_name};
+use crate::with_watcher_paused;
+
+/// Archives that would unpack to more than this are refused outright, and
+/// extraction stops once this much has been written whatever the archive
+/// claims.
+pub const MAX_UNCOMPRESSED_BYTES: u64 = 256 * 1024 * 1024;
+
+#[derive(serde::Serialize)]
+pub struct ArchiveEntry {
+    name: String,
+    size: u64,
+    escapes_root: bool,
+}
+
+#[derive(serde::Serialize)]
+pub struct ArchiveReport {
+    entries: Vec<ArchiveEntry>,
+    total_uncompressed: u64,
+    /// Account folders found, as written by `codex-account export`
+    /// (`<account>/<file>`).
+    accounts: Vec<String>,
+    red_flags: Vec<String>,
+    ok: bool,
+}
+
+/// True if extracting `name` under a root could land outside it (zip-slip).
+pub fn escapes_root(name: &str) -> bool {
+    let path = Path::new(name);
+    name.contains('\\')
+        || path.is_absolute()
+        || path
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+/// Copies `entry` into `out`, adding to `written` and failing once that would
+/// pass `limit`. Declared sizes can lie, so this reads at most one byte past
+/// what's left of the limit.
+pub fn copy_bounded(
+    entry: &mut impl Read,
+    out: &mut impl Write,
+    name: &str,
+    written: &mut u64,
+    limit: u64,
+) -> Result<(), String> {
+    let remaining = limit.saturating_sub(*written);
+    let copied = io::copy(&mut entry.take(remaining + 1), out)
+        .map_err(|e| format!("Failed to extract {}: {}", name, e))?;
+    if copied > remaining {
+        return Err(format!(
+            "Archive unpacks to more than {} bytes; stopped at {}",
+            limit, name
+        ));
+    }
+    *written += copied;
+    Ok(())
+}
+
+fn open_archive(src: &str) -> Result<zip::ZipArchive<fs::File>, String> {
+    let file = fs::File::open(src).map_err(|e| format!("Failed to open '{}': {}", src, e))?;
+    zip::ZipArchive::new(file).map_err(|e| format!("'{}' is not a valid zip archive: {}", src, e))
+}
+
+fn inspect<R: Read + Seek>(archive: &mut zip::ZipArchive<R>) -> Result<ArchiveReport, String> {
+    let mut entries = Vec::new();
+    let mut total_uncompressed = 0u64;
+    let mut accounts = BTreeSet::new();
+    let mut red_flags = Vec::new();
+
+    for i in 0..archive.len() {
+        let entry = archive
+            .by_index_raw(i)
+            .map_err(|e| format!("Failed to read archive entry {}: {}", i, e))?;
+        let name = entry.name().to_string();
+        let escapes = escapes_root(&name);
+        if escapes {
+            red_flags.push(format!(
+                "'{}' would extract outside the accounts root",
+                name
+            ));
+        }
+        if let Some((account, file)) = name.split_once('/') {
+            if !escapes && !file.is_empty() && !file.ends_with('/') {
+                accounts.insert(account.to_string());
+            }
+        }
+        total_uncompressed = total_uncompressed.saturating_add(entry.size());
+        entries.push(ArchiveEntry {
+            name,
+            size: entry.size(),
+            escapes_root: escapes,
+        });
+    }
+
+    if accounts.is_empty() {
+        red_flags.push("Archive has no account folders".to_string());
+    }
+    if total_uncompressed > MAX_UNCOMPRESSED_BYTES {
+        red_flags.push(format!(
+            "Archive unpacks to {} bytes (limit {})",
+            total_uncompressed, MAX_UNCOMPRESSED_BYTES
+        ));
+    }
+
+    Ok(ArchiveReport {
+        ok: red_flags.is_empty(),
+        entries,
+        total_uncompressed,
+        accounts: accounts.into_iter().collect(),
+        red_flags,
+    })
+}
+
+/// Lists an archive's contents and flags anything unsafe, without extracting.
+#[tauri::command]
+pub fn inspect_archive(src: String) -> Result<ArchiveReport, String> {
+    inspect(&mut open_archive(&src)?)
+}
+
+#[derive(serde::Serialize)]
+pub struct ArchiveImport {
+    imported: Vec<String>,
+    skipped: Vec<String>,
+}
+
+/// Imports the `<account>/<file>` entries of an export archive.
+///
+/// Refuses the whole archive if `inspect_archive` raises any red flag, and
+/// never overwrites an account that already exists.
+#[tauri::command]
+pub fn import_accounts(app: AppHandle, src: String) -> Result<ArchiveImport, String> {
+    let mut archive = open_archive(&src)?;
+    let report = inspect(&mut archive)?;
+    if !report.ok {
+        return Err(format!(
+            "Refusing to import '{}': {}",
+            src,
+            report.red_flags.join("; ")
+        ));
+    }
+
+    with_watcher_paused(&app, || {
+        extract_accounts(&mut archive, &accounts_dir(), MAX_UNCOMPRESSED_BYTES)
+    })
+}
+
+/// Extracts account entries under `root`, writing at most `limit` bytes in
+/// total. On failure the accounts created so far are removed again.
+fn extract_accounts<R: Read + Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    root: &Path,
+    limit: u64,
+) -> Result<ArchiveImport, String> {
+    let mut imported = BTreeSet::new();
+    let mut skipped = BTreeSet::new();
+    let mut written = 0u64;
+
+    let mut extract = || -> Result<(), String> {
+        for i in 0..archive.len() {
+            let mut entry = archive
+                .by_index(i)
+                .map_err(|e| format!("Failed to read archive entry {}: {}", i, e))?;
+            if entry.is_dir() {
+                continue;
+            }
+            let name = entry.name().to_string();
+            let Some((account, file)) = name.split_once('/') else {
+                continue; // top-level files like config.json are not account data
+            };
+            if skipped.contains(account) {
+                continue;
+            }
+            if validate_account_name(account).is_err() || file.is_empty() {
+                skipped.insert(account.to_string());
+                continue;
+            }
+
+            let dir = root.join(account);
+            if !imported.contains(account) && dir.exists() {
+                skipped.insert(account.to_string());
+                continue;
+            }
+
+            imported.insert(account.to_string());
+            let target = dir.join(file);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| format!("Failed to create {}: {}", parent.to_string_lossy(), e))?;
+            }
+            let mut out = fs::File::create(&target)
+                .map_err(|e| format!("Failed to create {}: {}", target.to_string_lossy(), e))?;
+            copy_bounded(&mut entry, &mut out, &name, &mut written, limit)?;
+        }
+        Ok(())
+    };
+    if let Err(e) = extract() {
+        for account in &imported {
+            let _ = fs::remove_dir_all(root.join(account));
+        }
+        return Err(e);
+    }
+
+    Ok(ArchiveImport {
+        imported: imported.into_iter().collect(),
+        skipped: skipped.into_iter().collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+
+    /// A zip laid out like `codex-account export` writes it.
+    fn export_zip(files: &[(&str, &[u8])]) -> zip::ZipArchive<Cursor<Vec<u8>>> {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+        for (name, bytes) in files {
+            zip.start_file(*name, options).unwrap();
+            zip.write_all(bytes).unwrap();
+        }
+        zip::ZipArchive::new(zip.finish().unwrap()).unwrap()
+    }
+
+    fn scratch(test: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("archive-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn export_layout_round_trips() {
+        let files: [(&str, &[u8]); 3] = [
+            ("work/auth.enc", b"ciphertext"),
+            ("work/metadata.json", b"{}"),
+            ("personal/auth.enc", b"other"),
+        ];
+        let mut archive = export_zip(&files);
+        let report = inspect(&mut archive).unwrap();
+        assert!(report.ok, "{:?}", report.red_flags);
+        assert_eq!(report.accounts, ["personal", "work"]);
+
+        let root = scratch("round-trip");
+        let import = extract_accounts(&mut archive, &root, MAX_UNCOMPRESSED_BYTES).unwrap();
+        assert_eq!(import.imported, ["personal", "work"]);
+        for (name, bytes) in files {
+            assert_eq!(fs::read(root.join(name)).unwrap(), bytes);
+        }
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn extraction_stops_at_the_limit() {
+        let mut archive = export_zip(&[("work/auth.enc", &[0u8; 64][..])]);
+        let root = scratch("limit");
+        assert!(extract_accounts(&mut archive, &root, 32).is_err());
+        assert!(!root.join("work").exists());
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn bounded_copy_counts_across_entries() {
+        let mut written = 0;
+        let mut out = Vec::new();
+        copy_bounded(&mut &[1u8; 24][..], &mut out, "a", &mut written, 32).unwrap();
+        assert_eq!(written, 24);
+        assert!(copy_bounded(&mut &[2u8; 16][..], &mut out, "b", &mut written, 32).is_err());
+        assert_eq!(out.len(), 24 + 9);
+    }
+}
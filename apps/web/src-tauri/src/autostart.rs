@@ -0,0 +1,87 @@
+//! Launch at login and background-only mode.
+//!
+//! Whether the app launches at login is kept by the OS (a LaunchAgent on
+//! macOS, the Run key on Windows, an XDG autostart entry on Linux), so the
+//! user can also turn it off from system settings; it isn't duplicated in
+//! `settings.json`. `background_only` is an ordinary setting: no Dock icon on
+//! macOS, and closing the window hides it instead of quitting.
+
+use tauri::{AppHandle, Runtime};
+
+use crate::settings::{load_settings, save_settings};
+use crate::update_tray;
+
+#[cfg(desktop)]
+pub fn is_enabled<R: Runtime>(app: &AppHandle<R>) -> bool {
+    use tauri_plugin_autostart::ManagerExt;
+    app.autolaunch().is_enabled().unwrap_or(false)
+}
+
+#[cfg(not(desktop))]
+pub fn is_enabled<R: Runtime>(_app: &AppHandle<R>) -> bool {
+    false
+}
+
+#[cfg(desktop)]
+fn set_enabled<R: Runtime>(app: &AppHandle<R>, enabled: bool) -> Result<(), String> {
+    use tauri_plugin_autostart::ManagerExt;
+    let autolaunch = app.autolaunch();
+    let result = if enabled {
+        autolaunch.enable()
+    } else {
+        autolaunch.disable()
+    };
+    result.map_err(|e| format!("Failed to update launch at login: {}", e))
+}
+
+#[cfg(not(desktop))]
+fn set_enabled<R: Runtime>(_app: &AppHandle<R>, _enabled: bool) -> Result<(), String> {
+    Err("Launch at login is only available on desktop".to_string())
+}
+
+/// Shows or hides the Dock icon to match `background_only` (macOS only).
+pub fn apply_activation_policy<R: Runtime>(app: &AppHandle<R>, background_only: bool) {
+    #[cfg(target_os = "macos")]
+    {
+        let policy = if background_only {
+            tauri::ActivationPolicy::Accessory
+        } else {
+            tauri::ActivationPolicy::Regular
+        };
+        if let Err(e) = app.set_activation_policy(policy) {
+            log::warn!("Failed to set activation policy: {}", e);
+        }
+    }
+    #[cfg(not(target_os = "macos"))]
+    let _ = (app, background_only);
+}
+
+/// Flips launch at login; used by the tray item.
+pub fn toggle<R: Runtime>(app: &AppHandle<R>) -> Result<(), String> {
+    set_enabled(app, !is_enabled(app))?;
+    let _ = update_tray(app);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_autostart(app: AppHandle) -> bool {
+    is_enabled(&app)
+}
+
+/// Turns launch at login on or off and returns the resulting state.
+#[tauri::command]
+pub fn set_autostart(app: AppHandle, enabled: bool) -> Result<bool, String> {
+    set_enabled(&app, enabled)?;
+    let _ = update_tray(&app);
+    Ok(is_enabled(&app))
+}
+
+#[tauri::command]
+pub fn set_background_only(app: AppHandle, enabled: bool) -> Result<(), String> {
+    let mut settings = load_settings();
+    settings.background_only = enabled;
+    save_settings(&settings)?;
+    apply_activation_policy(&app, enabled);
+    let _ = update_tray(&app);
+    Ok(())
+}
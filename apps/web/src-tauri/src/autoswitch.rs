@@ -0,0 +1,322 @@
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::health::now_secs;
+use crate::i18n::{limit_name, tr};
+use crate::session::{self, SessionPolicy, SwitchOutcome};
+use crate::settings::load_settings;
+use crate::{account_usage, codex_root, notify, update_tray, AppState};
+
+/// Usage (percent of either limit) at which the active account counts as
+/// exhausted when `config.json` doesn't set `auto_switch_threshold`.
+const DEFAULT_THRESHOLD: f64 = 100.0;
+
+/// Minimum time between two auto-switch evaluations, so a freshly chosen
+/// account isn't immediately judged on stale numbers and switched away from.
+const AUTO_SWITCH_COOLDOWN: Duration = Duration::from_secs(60);
+
+/// When auto-switch last ran; `None` until the first evaluation.
+#[derive(Default)]
+pub struct AutoSwitch(Mutex<Option<Instant>>);
+
+pub enum Rollover {
+    /// The active account is fine (or there is none).
+    Stay,
+    SwitchTo(String),
+    /// The active account is exhausted and so is every alternative.
+    NoCandidate,
+}
+
+/// Which of `usage`'s limits is at or past `threshold`, 5h first.
+fn exhausted_limit((p5, pw): (f64, f64), threshold: f64) -> Option<&'static str> {
+    if p5 >= threshold {
+        Some("5h")
+    } else if pw >= threshold {
+        Some("weekly")
+    } else {
+        None
+    }
+}
+
+/// Decides whether to leave `active`, given (5h, weekly) percentages per account.
+///
+/// `active` is exhausted once either limit reaches `threshold`. The replacement
+/// is the account whose worse limit is lowest, i.e. the one with the most
+/// remaining quota, and it must itself be below `threshold` on both. Accounts
+/// without usage data are never chosen, since their headroom is unknown.
+pub fn pick_rollover(
+    active: Option<&str>,
+    accounts: &[String],
+    usage: &HashMap<String, (f64, f64)>,
+    threshold: f64,
+) -> Rollover {
+    let Some(active) = active else {
+        return Rollover::Stay;
+    };
+    if !usage
+        .get(active)
+        .is_some_and(|u| exhausted_limit(*u, threshold).is_some())
+    {
+        return Rollover::Stay;
+    }
+
+    best_candidate(Some(active), accounts, usage, threshold)
+        .map_or(Rollover::NoCandidate, Rollover::SwitchTo)
+}
+
+/// The account other than `active` with the most remaining quota, below
+/// `threshold` on both limits: where a rollover would go next.
+pub fn best_candidate(
+    active: Option<&str>,
+    accounts: &[String],
+    usage: &HashMap<String, (f64, f64)>,
+    threshold: f64,
+) -> Option<String> {
+    accounts
+        .iter()
+        .filter(|name| Some(name.as_str()) != active)
+        .filter_map(|name| {
+            let (p5, pw) = *usage.get(name)?;
+            let worst = p5.max(pw);
+            (worst < threshold).then_some((name, worst))
+        })
+        .min_by(|(a, pa), (b, pb)| pa.total_cmp(pb).then_with(|| a.cmp(b)))
+        .map(|(name, _)| name.clone())
+}
+
+/// Where auto-switch would go next from the state's active account.
+pub fn recommended_next(state: &AppState) -> Option<String> {
+    best_candidate(
+        state.active_account.as_deref(),
+        &state.accounts,
+        &usage_by_account(state),
+        read_config().1,
+    )
+}
+
+fn usage_by_account(state: &AppState) -> HashMap<String, (f64, f64)> {
+    state
+        .accounts
+        .iter()
+        .filter_map(|name| Some((name.clone(), account_usage(state, name)?)))
+        .collect()
+}
+
+/// One auto-switch decision, appended to `autoswitch_log.jsonl`.
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct AutoSwitchEvent {
+    ts: i64,
+    from: String,
+    /// The account chosen; `None` when no account had headroom and nothing
+    /// was switched.
+    to: Option<String>,
+    /// The switch to `to` is waiting for a running Codex session to end.
+    #[serde(default)]
+    queued: bool,
+    limit: String,
+    usage: f64,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+fn log_path() -> PathBuf {
+    codex_root().join("autoswitch_log.jsonl")
+}
+
+fn record(event: &AutoSwitchEvent) {
+    let write = || -> Result<(), String> {
+        let line = serde_json::to_string(event).map_err(|e| e.to_string())?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(log_path())
+            .map_err(|e| e.to_string())?;
+        writeln!(file, "{}", line).map_err(|e| e.to_string())
+    };
+    if let Err(e) = write() {
+        log::warn!("Failed to record auto-switch: {}", e);
+    }
+}
+
+/// Past auto-switch decisions, newest first; `limit` caps how many.
+#[tauri::command]
+pub fn auto_switch_log(limit: Option<usize>) -> Vec<AutoSwitchEvent> {
+    let Ok(file) = fs::File::open(log_path()) else {
+        return Vec::new();
+    };
+    let mut events: Vec<AutoSwitchEvent> = BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect();
+    events.reverse();
+    events.truncate(limit.unwrap_or(usize::MAX));
+    events
+}
+
+/// `(auto_switch, threshold)` from the vault's `config.json`.
+fn read_config() -> (bool, f64) {
+    let config = fs::read_to_string(codex_root().join("config.json"))
+        .ok()
+        .and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok())
+        .unwrap_or_default();
+    (
+        config["auto_switch"].as_bool().unwrap_or(false),
+        config["auto_switch_threshold"]
+            .as_f64()
+            .unwrap_or(DEFAULT_THRESHOLD),
+    )
+}
+
+/// Runs after the watcher sees new usage numbers.
+pub fn evaluate<R: Runtime>(app: &AppHandle<R>, state: &AppState) {
+    let (enabled, threshold) = read_config();
+    if !enabled || load_settings().pause_automations {
+        return;
+    }
+    let usage = usage_by_account(state);
+    let Some(active) = state.active_account.as_deref() else {
+        return;
+    };
+    let Some(&active_usage) = usage.get(active) else {
+        return;
+    };
+    let Some(limit) = exhausted_limit(active_usage, threshold) else {
+        return;
+    };
+
+    // Only acting starts the cooldown, so a limit reached right after a quiet
+    // evaluation is still handled promptly.
+    let Some(control) = app.try_state::<AutoSwitch>() else {
+        return;
+    };
+    {
+        let Ok(mut last) = control.0.lock() else {
+            return;
+        };
+        if last.is_some_and(|t| t.elapsed() < AUTO_SWITCH_COOLDOWN) {
+            return;
+        }
+        *last = Some(Instant::now());
+    }
+    let used = if limit == "5h" {
+        active_usage.0
+    } else {
+        active_usage.1
+    };
+    let mut event = AutoSwitchEvent {
+        ts: now_secs(),
+        from: active.to_string(),
+        to: None,
+        queued: false,
+        limit: limit.to_string(),
+        usage: used,
+        error: None,
+    };
+
+    match pick_rollover(Some(active), &state.accounts, &usage, threshold) {
+        Rollover::Stay => return,
+        Rollover::SwitchTo(next) => match session::switch(app, &next, SessionPolicy::Queue) {
+            Ok(SwitchOutcome::Queued) => {
+                notify(
+                    app,
+                    &tr!("notify.switch_queued.title"),
+                    &tr!("notify.switch_queued.body", name = next),
+                );
+                event.to = Some(next);
+                event.queued = true;
+            }
+            Ok(SwitchOutcome::Switched) => {
+                notify(
+                    app,
+                    &tr!("notify.switched.title"),
+                    &tr!(
+                        "notify.switched.auto",
+                        from = active,
+                        usage = format!("{:.0}", used),
+                        limit = limit_name(limit),
+                        to = next,
+                    ),
+                );
+                event.to = Some(next);
+                let app_for_closure = app.clone();
+                let _ = app.run_on_main_thread(move || {
+                    let _ = update_tray(&app_for_closure);
+                });
+            }
+            Err(e) => {
+                log::error!("Auto-switch to '{}' failed: {}", next, e);
+                event.to = Some(next);
+                event.error = Some(e.to_string());
+            }
+        },
+        Rollover::NoCandidate => notify(
+            app,
+            &tr!("notify.exhausted.title"),
+            &tr!(
+                "notify.exhausted.body",
+                name = active,
+                limit = limit_name(limit),
+            ),
+        ),
+    }
+    record(&event);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup(usage: &[(&str, (f64, f64))]) -> (Vec<String>, HashMap<String, (f64, f64)>) {
+        let mut accounts: Vec<String> = usage.iter().map(|(name, _)| name.to_string()).collect();
+        accounts.push("unknown".to_string());
+        let usage = usage
+            .iter()
+            .map(|(name, u)| (name.to_string(), *u))
+            .collect();
+        (accounts, usage)
+    }
+
+    #[test]
+    fn exhausted_account_rolls_to_most_headroom() {
+        let (accounts, usage) = setup(&[
+            ("work", (100.0, 40.0)),
+            ("home", (30.0, 70.0)),
+            ("spare", (50.0, 10.0)),
+            ("full", (20.0, 100.0)),
+        ]);
+        assert!(matches!(
+            pick_rollover(Some("work"), &accounts, &usage, DEFAULT_THRESHOLD),
+            Rollover::SwitchTo(name) if name == "spare"
+        ));
+        assert!(matches!(
+            pick_rollover(Some("work"), &accounts, &usage, 60.0),
+            Rollover::SwitchTo(name) if name == "spare"
+        ));
+    }
+
+    #[test]
+    fn healthy_or_missing_active_stays() {
+        let (accounts, usage) = setup(&[("work", (99.0, 40.0)), ("home", (0.0, 0.0))]);
+        for active in [Some("work"), Some("unknown"), None] {
+            assert!(matches!(
+                pick_rollover(active, &accounts, &usage, DEFAULT_THRESHOLD),
+                Rollover::Stay
+            ));
+        }
+    }
+
+    #[test]
+    fn never_rolls_onto_an_exhausted_account() {
+        let (accounts, usage) = setup(&[("work", (100.0, 0.0)), ("home", (0.0, 100.0))]);
+        assert!(matches!(
+            pick_rollover(Some("work"), &accounts, &usage, DEFAULT_THRESHOLD),
+            Rollover::NoCandidate
+        ));
+    }
+}
@@ -0,0 +1,841 @@
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Cursor, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::AppHandle;
+
+use crate::accounts::{
+    account_dir, accounts_dir, apply_credentials, validate_account_name, write_private,
+};
+use crate::archive::escapes_root;
+use crate::i18n::tr;
+use crate::secrets::KEYCHAIN_MARKER;
+use crate::settings::load_settings;
+use crate::{codex_root, load_state, with_watcher_paused};
+
+/// Written alongside every snapshot: relative file path -> SHA-256 hex digest.
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+#[derive(serde::Serialize, serde::Deserialize, Default)]
+pub struct BackupManifest {
+    #[serde(default)]
+    pub files: BTreeMap<String, String>,
+}
+
+pub fn backups_root() -> PathBuf {
+    codex_root().join("backups")
+}
+
+/// Snapshots live at `backups/<account>/<timestamp>/`.
+pub fn backup_dir(account: &str, timestamp: &str) -> PathBuf {
+    backups_root().join(account).join(timestamp)
+}
+
+fn validate_timestamp(timestamp: &str) -> Result<(), String> {
+    if timestamp.is_empty()
+        || !timestamp
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+    {
+        return Err(tr!("error.backup_timestamp_invalid", timestamp = timestamp));
+    }
+    Ok(())
+}
+
+pub fn hash_file(path: &Path) -> Result<String, String> {
+    let bytes =
+        fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.to_string_lossy(), e))?;
+    Ok(format!("{:x}", Sha256::digest(&bytes)))
+}
+
+/// Every file under `dir`, keyed by its `/`-separated path relative to `dir`.
+pub fn list_files(dir: &Path) -> Result<BTreeMap<String, PathBuf>, String> {
+    fn walk(base: &Path, dir: &Path, out: &mut BTreeMap<String, PathBuf>) -> Result<(), String> {
+        let entries = fs::read_dir(dir)
+            .map_err(|e| format!("Failed to read {}: {}", dir.to_string_lossy(), e))?;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                walk(base, &path, out)?;
+            } else if let Ok(rel) = path.strip_prefix(base) {
+                let key = rel
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                out.insert(key, path);
+            }
+        }
+        Ok(())
+    }
+
+    let mut out = BTreeMap::new();
+    walk(dir, dir, &mut out)?;
+    Ok(out)
+}
+
+/// Hashes every file under `dir`, keyed by its `/`-separated path relative to `dir`.
+pub fn hash_tree(dir: &Path) -> Result<BTreeMap<String, String>, String> {
+    list_files(dir)?
+        .into_iter()
+        .filter(|(key, _)| key != MANIFEST_FILE)
+        .map(|(key, path)| Ok((key, hash_file(&path)?)))
+        .collect()
+}
+
+/// Parses a backup's manifest. Its keys are joined onto the backup and
+/// account dirs, so one that could leave them rejects the whole manifest.
+fn parse_manifest(content: &str, label: &str) -> Result<BackupManifest, String> {
+    let manifest: BackupManifest = serde_json::from_str(content)
+        .map_err(|e| format!("Corrupt manifest in backup '{}': {}", label, e))?;
+    if let Some(file) = manifest.files.keys().find(|file| escapes_root(file)) {
+        return Err(format!(
+            "Manifest in backup '{}' lists '{}', outside the account dir",
+            label, file
+        ));
+    }
+    Ok(manifest)
+}
+
+/// Loads a snapshot's manifest, hashing the files directly for snapshots that
+/// predate manifests.
+pub fn load_manifest(account: &str, timestamp: &str) -> Result<BackupManifest, String> {
+    validate_account_name(account)?;
+    validate_timestamp(timestamp)?;
+
+    let dir = backup_dir(account, timestamp);
+    if !dir.is_dir() {
+        return Err(tr!(
+            "error.backup_not_found",
+            backup = timestamp,
+            name = account
+        ));
+    }
+
+    let manifest_path = dir.join(MANIFEST_FILE);
+    if manifest_path.exists() {
+        let content = fs::read_to_string(&manifest_path)
+            .map_err(|e| format!("Failed to read manifest: {}", e))?;
+        return parse_manifest(&content, timestamp);
+    }
+
+    Ok(BackupManifest {
+        files: hash_tree(&dir)?,
+    })
+}
+
+#[derive(serde::Serialize)]
+pub struct BackupDiff {
+    account: String,
+    from: String,
+    to: String,
+    added: Vec<String>,
+    removed: Vec<String>,
+    changed: Vec<String>,
+    unchanged: usize,
+}
+
+/// Compares two snapshots of the same account by file set and content hash.
+#[tauri::command]
+pub fn diff_backups(account: String, a: String, b: String) -> Result<BackupDiff, String> {
+    let from = load_manifest(&account, &a)?;
+    let to = load_manifest(&account, &b)?;
+
+    let mut diff = BackupDiff {
+        account,
+        from: a,
+        to: b,
+        added: Vec::new(),
+        removed: Vec::new(),
+        changed: Vec::new(),
+        unchanged: 0,
+    };
+
+    for (file, hash) in &from.files {
+        match to.files.get(file) {
+            None => diff.removed.push(file.clone()),
+            Some(other) if other != hash => diff.changed.push(file.clone()),
+            Some(_) => diff.unchanged += 1,
+        }
+    }
+    diff.added = to
+        .files
+        .keys()
+        .filter(|f| !from.files.contains_key(*f))
+        .cloned()
+        .collect();
+
+    Ok(diff)
+}
+
+/// Sortable UTC timestamp naming a new snapshot or archive.
+pub fn new_timestamp() -> String {
+    chrono::Utc::now().format("%Y%m%dT%H%M%S%3fZ").to_string()
+}
+
+/// Zips `accounts/<name>/` to `backups/<name>/<timestamp>.zip` and returns the
+/// archive's path. Entries are stored as `<name>/<file>`, the same layout
+/// `import_accounts` reads, next to a top-level `manifest.json` of hashes.
+#[tauri::command]
+pub fn backup_account(name: String) -> Result<String, String> {
+    validate_account_name(&name)?;
+    let source = account_dir(&name);
+    if !source.is_dir() {
+        return Err(tr!("error.account_not_found", name = name));
+    }
+    let archive = zip_account(&name, &source)?;
+
+    let timestamp = new_timestamp();
+    let dest = backups_root()
+        .join(&name)
+        .join(format!("{}.zip", timestamp));
+    write_private(&dest, &archive)?;
+    Ok(dest.to_string_lossy().into_owned())
+}
+
+/// The `backup_account` archive of the account `name` held in `source`.
+fn zip_account(name: &str, source: &Path) -> Result<Vec<u8>, String> {
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    let mut manifest = BackupManifest::default();
+    for (rel, path) in list_files(source)? {
+        let bytes = fs::read(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.to_string_lossy(), e))?;
+        zip.start_file(format!("{}/{}", name, rel), options)
+            .map_err(|e| format!("Failed to archive {}: {}", rel, e))?;
+        zip.write_all(&bytes)
+            .map_err(|e| format!("Failed to archive {}: {}", rel, e))?;
+        manifest
+            .files
+            .insert(rel, format!("{:x}", Sha256::digest(&bytes)));
+    }
+    let body = serde_json::to_vec_pretty(&manifest)
+        .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    zip.start_file(MANIFEST_FILE, options)
+        .map_err(|e| format!("Failed to archive manifest: {}", e))?;
+    zip.write_all(&body)
+        .map_err(|e| format!("Failed to archive manifest: {}", e))?;
+    Ok(zip
+        .finish()
+        .map_err(|e| format!("Failed to finish archive: {}", e))?
+        .into_inner())
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackupKind {
+    /// A `backups/<account>/<timestamp>/` directory from `create_backup`.
+    Snapshot,
+    /// A `backups/<account>/<timestamp>.zip` from `backup_account`.
+    Archive,
+}
+
+#[derive(serde::Serialize)]
+pub struct BackupEntry {
+    file_name: String,
+    kind: BackupKind,
+    size: u64,
+}
+
+fn backup_entry(entry: &fs::DirEntry) -> Option<BackupEntry> {
+    let file_name = entry.file_name().to_str()?.to_string();
+    let path = entry.path();
+    if path.is_dir() {
+        let size = list_files(&path)
+            .ok()?
+            .values()
+            .filter_map(|p| p.metadata().ok())
+            .map(|m| m.len())
+            .sum();
+        Some(BackupEntry {
+            file_name,
+            kind: BackupKind::Snapshot,
+            size,
+        })
+    } else if path.extension().is_some_and(|ext| ext == "zip") {
+        Some(BackupEntry {
+            file_name,
+            kind: BackupKind::Archive,
+            size: entry.metadata().ok()?.len(),
+        })
+    } else {
+        None
+    }
+}
+
+/// Snapshots and archives for `name`, newest first.
+#[tauri::command]
+pub fn list_backups(name: String) -> Result<Vec<BackupEntry>, String> {
+    validate_account_name(&name)?;
+    let mut backups: Vec<BackupEntry> = match fs::read_dir(backups_root().join(&name)) {
+        Ok(entries) => entries.flatten().filter_map(|e| backup_entry(&e)).collect(),
+        Err(_) => Vec::new(),
+    };
+    backups.sort_by(|a, b| b.file_name.cmp(&a.file_name));
+    Ok(backups)
+}
+
+/// The single account an archive holds, from its `<account>/<file>` entries.
+fn archive_account(archive: &mut zip::ZipArchive<fs::File>) -> Result<String, String> {
+    let mut account: Option<String> = None;
+    for i in 0..archive.len() {
+        let entry = archive
+            .by_index_raw(i)
+            .map_err(|e| format!("Failed to read archive entry {}: {}", i, e))?;
+        let name = entry.name();
+        if name == MANIFEST_FILE {
+            continue;
+        }
+        if escapes_root(name) {
+            return Err(format!("Archive entry '{}' escapes the account dir", name));
+        }
+        let Some((owner, _)) = name.split_once('/') else {
+            return Err(format!("Archive entry '{}' is not inside an account", name));
+        };
+        match &account {
+            Some(existing) if existing != owner => {
+                return Err("Archive contains more than one account".to_string())
+            }
+            Some(_) => {}
+            None => account = Some(owner.to_string()),
+        }
+    }
+    let account = account.ok_or("Archive is empty")?;
+    validate_account_name(&account)?;
+    Ok(account)
+}
+
+fn extract_into(archive: &mut zip::ZipArchive<fs::File>, dest: &Path) -> Result<(), String> {
+    for i in 0..archive.len() {
+        let mut entry = archive
+            .by_index(i)
+            .map_err(|e| format!("Failed to read archive entry {}: {}", i, e))?;
+        let name = entry.name().to_string();
+        let rel = name.split_once('/').map_or("", |(_, rel)| rel);
+        if entry.is_dir() || rel.is_empty() {
+            continue;
+        }
+        let target = dest.join(rel);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.to_string_lossy(), e))?;
+        }
+        let mut out = fs::File::create(&target)
+            .map_err(|e| format!("Failed to create {}: {}", target.to_string_lossy(), e))?;
+        io::copy(&mut entry, &mut out).map_err(|e| format!("Failed to extract {}: {}", name, e))?;
+    }
+    Ok(())
+}
+
+/// Restores an account from a `backup_account` archive and returns its name.
+///
+/// Only archives under `backups/` are accepted. An existing, non-empty account
+/// is replaced only when `overwrite` is set; the archive is fully extracted to
+/// a staging dir first, so a bad archive never leaves a half-restored account.
+#[tauri::command]
+pub fn restore_account(
+    app: AppHandle,
+    archive_path: String,
+    overwrite: Option<bool>,
+) -> Result<String, String> {
+    let path = fs::canonicalize(&archive_path)
+        .map_err(|e| format!("Cannot open '{}': {}", archive_path, e))?;
+    let root =
+        fs::canonicalize(backups_root()).map_err(|e| format!("Cannot open backups dir: {}", e))?;
+    if !path.starts_with(&root) {
+        return Err(format!(
+            "'{}' is not inside the backups directory",
+            archive_path
+        ));
+    }
+
+    let file =
+        fs::File::open(&path).map_err(|e| format!("Failed to open '{}': {}", archive_path, e))?;
+    let mut archive = zip::ZipArchive::new(file)
+        .map_err(|e| format!("'{}' is not a valid zip archive: {}", archive_path, e))?;
+    let name = archive_account(&mut archive)?;
+    let report = verify(&path, archive_path.clone())?;
+    if report.has_manifest && !report.ok {
+        return Err(format!(
+            "Backup '{}' is damaged: {}",
+            archive_path,
+            report.problems().join(", ")
+        ));
+    }
+
+    let dest = account_dir(&name);
+    ensure_replaceable(&name, &dest, overwrite.unwrap_or(false))?;
+
+    // Resuming the watcher afterwards rebuilds the tray with the restored account.
+    with_watcher_paused(&app, || {
+        let staging = accounts_dir().join(format!(".restore-{}", name));
+        let _ = fs::remove_dir_all(&staging);
+        if let Err(e) = extract_into(&mut archive, &staging) {
+            let _ = fs::remove_dir_all(&staging);
+            return Err(e);
+        }
+        if let Err(e) = replace_dir(&staging, &dest) {
+            let _ = fs::remove_dir_all(&staging);
+            return Err(tr!("error.restore_failed", name = name, error = e));
+        }
+        Ok(name.clone())
+    })
+}
+
+/// Refuses to restore over a non-empty account folder unless `overwrite`.
+fn ensure_replaceable(name: &str, dest: &Path, overwrite: bool) -> Result<(), String> {
+    let occupied = fs::read_dir(dest).is_ok_and(|mut entries| entries.next().is_some());
+    if occupied && !overwrite {
+        return Err(tr!("error.restore_exists", name = name));
+    }
+    Ok(())
+}
+
+/// Moves `staging` to `dest`. An existing `dest` is set aside as
+/// `.restore-old-<name>` and only deleted once `staging` is in place; if that
+/// fails it is moved back.
+pub fn replace_dir(staging: &Path, dest: &Path) -> Result<(), String> {
+    let name = dest
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let old = dest.with_file_name(format!(".restore-old-{}", name));
+    let replacing = dest.exists();
+    if replacing {
+        let _ = fs::remove_dir_all(&old);
+        fs::rename(dest, &old).map_err(|e| format!("Failed to set aside the old copy: {}", e))?;
+    }
+    if let Err(e) = fs::rename(staging, dest) {
+        if replacing {
+            if let Err(re) = fs::rename(&old, dest) {
+                log::error!(
+                    "Failed to put back {} after a failed restore: {}",
+                    dest.to_string_lossy(),
+                    re
+                );
+            }
+        }
+        return Err(e.to_string());
+    }
+    if replacing {
+        let _ = fs::remove_dir_all(&old);
+    }
+    Ok(())
+}
+
+/// Credential files a snapshot captures, when present.
+const SNAPSHOT_FILES: &[&str] = &["auth.json", "auth.enc", KEYCHAIN_MARKER, "config.json"];
+
+/// Copies `account`'s credential files into a new snapshot and returns its
+/// timestamp. The snapshot's manifest records each file's hash.
+pub fn snapshot_account(account: &str) -> Result<String, String> {
+    validate_account_name(account)?;
+    let source = account_dir(account);
+    if !source.is_dir() {
+        return Err(tr!("error.account_not_found", name = account));
+    }
+
+    let timestamp = new_timestamp();
+    let dest = backup_dir(account, &timestamp);
+    let mut manifest = BackupManifest::default();
+    for file in SNAPSHOT_FILES {
+        let path = source.join(file);
+        if !path.is_file() {
+            continue;
+        }
+        let bytes = fs::read(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.to_string_lossy(), e))?;
+        write_private(&dest.join(file), &bytes)?;
+        manifest
+            .files
+            .insert(file.to_string(), format!("{:x}", Sha256::digest(&bytes)));
+    }
+    if manifest.files.is_empty() {
+        return Err(tr!("error.account_no_credentials", name = account));
+    }
+
+    let body = serde_json::to_vec_pretty(&manifest)
+        .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    write_private(&dest.join(MANIFEST_FILE), &body)?;
+    Ok(timestamp)
+}
+
+#[tauri::command]
+pub fn create_backup(account: String) -> Result<String, String> {
+    let timestamp = snapshot_account(&account)?;
+    apply_retention(&account);
+    Ok(timestamp)
+}
+
+/// Copies a snapshot's files back into `accounts/<account>/`, after checking
+/// them against the manifest. Restoring the active account also refreshes
+/// the live Codex credentials.
+#[tauri::command]
+pub fn restore_backup(app: AppHandle, account: String, timestamp: String) -> Result<(), String> {
+    let manifest = load_manifest(&account, &timestamp)?;
+    let source = backup_dir(&account, &timestamp);
+    for (file, hash) in &manifest.files {
+        if &hash_file(&source.join(file))? != hash {
+            return Err(format!(
+                "Backup '{}' is damaged: {} does not match its manifest",
+                timestamp, file
+            ));
+        }
+    }
+
+    with_watcher_paused(&app, || {
+        let dest = account_dir(&account);
+        for file in manifest.files.keys() {
+            let bytes = fs::read(source.join(file))
+                .map_err(|e| format!("Failed to read backup of {}: {}", file, e))?;
+            write_private(&dest.join(file), &bytes)?;
+        }
+        let is_active = load_state().active_account.as_deref() == Some(account.as_str());
+        if is_active && manifest.files.contains_key("auth.json") {
+            apply_credentials(&account)?;
+        }
+        Ok(())
+    })
+}
+
+/// Prunes `account`'s snapshots and archives per the retention settings.
+/// The newest backup is always kept; failures are logged, never fatal.
+pub fn apply_retention(account: &str) {
+    apply_retention_in(&backups_root().join(account));
+}
+
+/// `apply_retention` for any directory of timestamped backups.
+pub fn apply_retention_in(dir: &Path) {
+    let retention = load_settings().backup_retention;
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut backups: Vec<(String, PathBuf)> = entries
+        .flatten()
+        .filter(|e| backup_entry(e).is_some())
+        .filter_map(|e| Some((e.file_name().to_str()?.to_string(), e.path())))
+        .collect();
+    backups.sort_by(|a, b| b.0.cmp(&a.0));
+
+    let max_age = retention
+        .max_age_days
+        .map(|days| Duration::from_secs(days * 24 * 60 * 60));
+    for (index, (_, path)) in backups.iter().enumerate().skip(1) {
+        let too_many = retention.keep_last.is_some_and(|keep| index >= keep);
+        let too_old = max_age.is_some_and(|max_age| {
+            fs::metadata(path)
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|modified| modified.elapsed().ok())
+                .is_some_and(|age| age > max_age)
+        });
+        if too_many || too_old {
+            let removed = if path.is_dir() {
+                fs::remove_dir_all(path)
+            } else {
+                fs::remove_file(path)
+            };
+            if let Err(e) = removed {
+                log::warn!("Failed to prune backup {}: {}", path.to_string_lossy(), e);
+            }
+        }
+    }
+}
+
+/// `<account>/<file_name>` as shown by `list_backups`.
+fn parse_backup_id(id: &str) -> Result<(String, String), String> {
+    let (account, file_name) = id
+        .split_once('/')
+        .ok_or_else(|| format!("Invalid backup id '{}'", id))?;
+    validate_account_name(account)?;
+    validate_timestamp(file_name.strip_suffix(".zip").unwrap_or(file_name))?;
+    Ok((account.to_string(), file_name.to_string()))
+}
+
+/// What a backup actually holds (file -> hash) and what its manifest says.
+struct BackupContents {
+    kind: BackupKind,
+    actual: BTreeMap<String, String>,
+    manifest: Option<BackupManifest>,
+}
+
+fn backup_path(account: &str, file_name: &str) -> Result<PathBuf, String> {
+    let path = backups_root().join(account).join(file_name);
+    if !path.exists() {
+        return Err(tr!(
+            "error.backup_not_found",
+            backup = file_name,
+            name = account
+        ));
+    }
+    Ok(path)
+}
+
+fn read_backup(path: &Path) -> Result<BackupContents, String> {
+    let label = path
+        .file_name()
+        .map_or_else(|| path.to_string_lossy(), |name| name.to_string_lossy());
+    if path.is_dir() {
+        let manifest_path = path.join(MANIFEST_FILE);
+        let manifest = if manifest_path.exists() {
+            let content = fs::read_to_string(&manifest_path)
+                .map_err(|e| format!("Failed to read manifest: {}", e))?;
+            Some(parse_manifest(&content, &label)?)
+        } else {
+            None
+        };
+        return Ok(BackupContents {
+            kind: BackupKind::Snapshot,
+            actual: hash_tree(path)?,
+            manifest,
+        });
+    }
+
+    let file = fs::File::open(path).map_err(|e| format!("Failed to open backup: {}", e))?;
+    let mut archive =
+        zip::ZipArchive::new(file).map_err(|e| format!("Backup is not a valid zip: {}", e))?;
+    let mut actual = BTreeMap::new();
+    let mut manifest = None;
+    for i in 0..archive.len() {
+        let mut entry = archive
+            .by_index(i)
+            .map_err(|e| format!("Failed to read archive entry {}: {}", i, e))?;
+        if entry.is_dir() {
+            continue;
+        }
+        let name = entry.name().to_string();
+        let mut bytes = Vec::new();
+        io::Read::read_to_end(&mut entry, &mut bytes)
+            .map_err(|e| format!("Failed to read {}: {}", name, e))?;
+        if name == MANIFEST_FILE {
+            manifest = Some(parse_manifest(&String::from_utf8_lossy(&bytes), &label)?);
+        } else if let Some((_, rel)) = name.split_once('/') {
+            actual.insert(rel.to_string(), format!("{:x}", Sha256::digest(&bytes)));
+        }
+    }
+    Ok(BackupContents {
+        kind: BackupKind::Archive,
+        actual,
+        manifest,
+    })
+}
+
+#[derive(serde::Serialize)]
+pub struct BackupVerification {
+    id: String,
+    /// Backups made before manifests existed can't be verified.
+    has_manifest: bool,
+    ok: bool,
+    /// Listed in the manifest but absent.
+    missing: Vec<String>,
+    /// Present but with a different hash than recorded.
+    corrupted: Vec<String>,
+    /// Present but not in the manifest.
+    unexpected: Vec<String>,
+}
+
+impl BackupVerification {
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        for (label, files) in [
+            ("missing", &self.missing),
+            ("corrupted", &self.corrupted),
+            ("unexpected", &self.unexpected),
+        ] {
+            if !files.is_empty() {
+                problems.push(format!("{} {}", label, files.join(", ")));
+            }
+        }
+        problems
+    }
+}
+
+fn verify(path: &Path, id: String) -> Result<BackupVerification, String> {
+    let contents = read_backup(path)?;
+    let mut report = BackupVerification {
+        id,
+        has_manifest: contents.manifest.is_some(),
+        ok: false,
+        missing: Vec::new(),
+        corrupted: Vec::new(),
+        unexpected: Vec::new(),
+    };
+    let Some(manifest) = contents.manifest else {
+        return Ok(report);
+    };
+    for (file, hash) in &manifest.files {
+        match contents.actual.get(file) {
+            None => report.missing.push(file.clone()),
+            Some(actual) if actual != hash => report.corrupted.push(file.clone()),
+            Some(_) => {}
+        }
+    }
+    report.unexpected = contents
+        .actual
+        .keys()
+        .filter(|f| !manifest.files.contains_key(*f))
+        .cloned()
+        .collect();
+    report.ok = report.problems().is_empty();
+    Ok(report)
+}
+
+/// Checks a backup (`<account>/<file_name>`) against its SHA-256 manifest.
+#[tauri::command]
+pub fn verify_backup(id: String) -> Result<BackupVerification, String> {
+    let (account, file_name) = parse_backup_id(&id)?;
+    verify(&backup_path(&account, &file_name)?, id)
+}
+
+#[derive(serde::Serialize)]
+pub struct RestorePreview {
+    id: String,
+    kind: BackupKind,
+    verification: BackupVerification,
+    /// In the backup but not in the account now.
+    added: Vec<String>,
+    modified: Vec<String>,
+    /// In the account now but not in the backup; only archives remove these.
+    removed: Vec<String>,
+    /// Left as is (snapshots only restore the files they hold).
+    kept: Vec<String>,
+    unchanged: usize,
+    /// The account is active, so restoring also rewrites the live Codex credentials.
+    updates_live_credentials: bool,
+}
+
+/// What restoring backup `id` would change in its account, without changing it.
+#[tauri::command]
+pub fn preview_restore(id: String) -> Result<RestorePreview, String> {
+    let (account, file_name) = parse_backup_id(&id)?;
+    let path = backup_path(&account, &file_name)?;
+    let verification = verify(&path, id.clone())?;
+    let contents = read_backup(&path)?;
+    let dir = account_dir(&account);
+    let current = if dir.is_dir() {
+        hash_tree(&dir)?
+    } else {
+        BTreeMap::new()
+    };
+
+    let mut preview = RestorePreview {
+        id,
+        verification,
+        added: Vec::new(),
+        modified: Vec::new(),
+        removed: Vec::new(),
+        kept: Vec::new(),
+        unchanged: 0,
+        updates_live_credentials: false,
+        kind: contents.kind,
+    };
+    for (file, hash) in &contents.actual {
+        match current.get(file) {
+            None => preview.added.push(file.clone()),
+            Some(now) if now != hash => preview.modified.push(file.clone()),
+            Some(_) => preview.unchanged += 1,
+        }
+    }
+    let leftover = current
+        .keys()
+        .filter(|f| !contents.actual.contains_key(*f))
+        .cloned();
+    match preview.kind {
+        BackupKind::Snapshot => preview.kept = leftover.collect(),
+        BackupKind::Archive => preview.removed = leftover.collect(),
+    }
+
+    let touches_auth = |f: &String| f == "auth.json";
+    preview.updates_live_credentials = load_state().active_account.as_deref()
+        == Some(account.as_str())
+        && (preview.added.iter().any(touches_auth) || preview.modified.iter().any(touches_auth));
+    Ok(preview)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("backup-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn restore_refuses_to_overwrite_without_flag() {
+        let dir = scratch("refuse");
+        let dest = dir.join("work");
+        fs::create_dir_all(&dest).unwrap();
+        fs::write(dest.join("auth.json"), "old").unwrap();
+
+        assert!(ensure_replaceable("work", &dest, false).is_err());
+        assert!(ensure_replaceable("work", &dest, true).is_ok());
+        assert!(ensure_replaceable("new", &dir.join("new"), false).is_ok());
+        assert_eq!(fs::read_to_string(dest.join("auth.json")).unwrap(), "old");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn restore_with_overwrite_replaces_contents() {
+        let dir = scratch("overwrite");
+        let dest = dir.join("work");
+        fs::create_dir_all(&dest).unwrap();
+        fs::write(dest.join("auth.json"), "old").unwrap();
+        fs::write(dest.join("stale.txt"), "gone").unwrap();
+        let staging = dir.join(".restore-work");
+        fs::create_dir_all(&staging).unwrap();
+        fs::write(staging.join("auth.json"), "new").unwrap();
+
+        replace_dir(&staging, &dest).unwrap();
+
+        assert_eq!(fs::read_to_string(dest.join("auth.json")).unwrap(), "new");
+        assert!(!dest.join("stale.txt").exists());
+        assert!(!staging.exists());
+        assert!(!dir.join(".restore-old-work").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn manifest_keys_cannot_leave_the_account() {
+        let good = r#"{"files":{"auth.json":"ab","sub/config.json":"cd"}}"#;
+        assert_eq!(parse_manifest(good, "t").unwrap().files.len(), 2);
+        for key in ["../../.codex/auth.json", "/etc/passwd", r"..\auth.json"] {
+            let content = serde_json::json!({ "files": { key: "ab" } }).to_string();
+            assert!(parse_manifest(&content, "t").is_err(), "{}", key);
+        }
+    }
+
+    #[test]
+    fn backup_restores_byte_for_byte() {
+        let dir = scratch("round-trip");
+        let account = dir.join("work");
+        let files: [(&str, &[u8]); 2] = [
+            ("auth.json", br#"{"token":"secret"}"#),
+            ("sessions/config.json", b"{}"),
+        ];
+        for (rel, bytes) in files {
+            fs::create_dir_all(account.join(rel).parent().unwrap()).unwrap();
+            fs::write(account.join(rel), bytes).unwrap();
+        }
+        let zip_path = dir.join("backup.zip");
+        fs::write(&zip_path, zip_account("work", &account).unwrap()).unwrap();
+        fs::remove_dir_all(&account).unwrap();
+
+        let mut archive = zip::ZipArchive::new(fs::File::open(&zip_path).unwrap()).unwrap();
+        assert_eq!(archive_account(&mut archive).unwrap(), "work");
+        let staging = dir.join(".restore-work");
+        extract_into(&mut archive, &staging).unwrap();
+        replace_dir(&staging, &account).unwrap();
+
+        for (rel, bytes) in files {
+            assert_eq!(fs::read(account.join(rel)).unwrap(), bytes);
+        }
+        assert!(!account.join(MANIFEST_FILE).exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
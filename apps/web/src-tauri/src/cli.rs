@@ -0,0 +1,115 @@
+//! External control through `codex-switch://` deep links and command-line flags.
+//!
+//! - `codex-switch://switch/<account>` or `--switch <account>` switches accounts.
+//! - `codex-switch://backup` or `--backup` backs up the whole vault.
+//! - `--list` and `--status` print to stdout and exit. They only read the
+//!   vault, so they answer directly instead of going through the running app.
+//!
+//! A second launch hands its arguments to the running instance through the
+//! single-instance plugin, so scripts can drive the tray app.
+
+use tauri::AppHandle;
+
+use crate::i18n::tr;
+use crate::{account_usage, load_state, notify, schedule, switch_from_ui};
+
+pub const SCHEME: &str = "codex-switch";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RemoteAction {
+    Switch(String),
+    Backup,
+}
+
+/// `codex-switch://switch/<account>` or `codex-switch://backup`.
+pub fn parse_url(url: &str) -> Option<RemoteAction> {
+    let rest = url.strip_prefix(SCHEME)?.strip_prefix("://")?;
+    let mut parts = rest.trim_end_matches('/').splitn(2, '/');
+    match (parts.next()?, parts.next()) {
+        ("switch", Some(account)) if !account.is_empty() => {
+            Some(RemoteAction::Switch(account.to_string()))
+        }
+        ("backup", None) => Some(RemoteAction::Backup),
+        _ => None,
+    }
+}
+
+/// Actions requested by `--switch <account>` and `--backup`. Deep-link URLs
+/// are included when `urls` is set.
+pub fn parse_args(args: &[String], urls: bool) -> Vec<RemoteAction> {
+    let mut actions = Vec::new();
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--switch" => match iter.next() {
+                Some(account) => actions.push(RemoteAction::Switch(account.clone())),
+                None => log::warn!("--switch needs an account name"),
+            },
+            "--backup" => actions.push(RemoteAction::Backup),
+            _ if urls => actions.extend(parse_url(arg)),
+            _ => {}
+        }
+    }
+    actions
+}
+
+fn usage_suffix(state: &crate::AppState, name: &str) -> String {
+    account_usage(state, name)
+        .map(|(p5, pw)| format!(" [5h: {:.0}% / W: {:.0}%]", p5, pw))
+        .unwrap_or_default()
+}
+
+/// Answers `--list` / `--status` and returns the exit code, or `None` when
+/// neither was passed and the app should start normally.
+pub fn run_local(args: &[String]) -> Option<i32> {
+    let list = args.iter().any(|a| a == "--list");
+    let status = args.iter().any(|a| a == "--status");
+    if !list && !status {
+        return None;
+    }
+
+    let state = load_state();
+    if list {
+        for name in &state.accounts {
+            let marker = if state.active_account.as_deref() == Some(name.as_str()) {
+                "*"
+            } else {
+                " "
+            };
+            println!("{} {}{}", marker, name, usage_suffix(&state, name));
+        }
+    }
+    if status {
+        match &state.active_account {
+            Some(active) => println!("Active: {}{}", active, usage_suffix(&state, active)),
+            None => println!("Active: none"),
+        }
+    }
+    Some(0)
+}
+
+pub fn execute(app: &AppHandle, action: RemoteAction) {
+    match action {
+        RemoteAction::Switch(account) => {
+            let _ = switch_from_ui(app, &account);
+        }
+        RemoteAction::Backup => match schedule::backup_vault_now(app.clone()) {
+            Ok(_) => notify(
+                app,
+                &tr!("notify.backup_created.title"),
+                &tr!("notify.backup_created.vault"),
+            ),
+            Err(e) => {
+                log::error!("Vault backup failed: {}", e);
+                notify(app, &tr!("notify.backup_failed.title"), &e);
+            }
+        },
+    }
+}
+
+/// Runs every action in `args`; see `parse_args` for `urls`.
+pub fn handle_args(app: &AppHandle, args: &[String], urls: bool) {
+    for action in parse_args(args, urls) {
+        execute(app, action);
+    }
+}
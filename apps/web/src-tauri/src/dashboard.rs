@@ -0,0 +1,124 @@
+//! Usage across all accounts at once, for the dashboard view and the tray
+//! header.
+
+use tauri::AppHandle;
+
+use crate::health::now_secs;
+use crate::i18n::tr;
+use crate::usage::UsageWindow;
+use crate::{autoswitch, current_state, mock, sort_by_headroom, AppState};
+
+/// One window summed over every account that reports its limit.
+#[derive(serde::Serialize, Default)]
+pub struct WindowTotal {
+    remaining: f64,
+    limit: f64,
+    /// How many accounts contributed.
+    accounts: usize,
+}
+
+impl WindowTotal {
+    fn add(&mut self, window: Option<&UsageWindow>) {
+        let Some((remaining, limit)) = window.and_then(|w| Some((w.remaining()?, w.limit?))) else {
+            return;
+        };
+        self.remaining += remaining;
+        self.limit += limit;
+        self.accounts += 1;
+    }
+
+    /// Share of the combined limit still available, in percent.
+    pub fn percent_remaining(&self) -> Option<f64> {
+        (self.limit > 0.0).then(|| self.remaining / self.limit * 100.0)
+    }
+}
+
+#[derive(serde::Serialize)]
+pub struct AccountRemaining {
+    name: String,
+    /// Display name from the account's metadata, or the name.
+    title: String,
+    active: bool,
+    remaining_5h: Option<f64>,
+    remaining_weekly: Option<f64>,
+    /// Percent of each limit still available.
+    percent_left_5h: Option<f64>,
+    percent_left_weekly: Option<f64>,
+    /// Unix seconds of the next reset of the fuller window.
+    next_reset: Option<i64>,
+}
+
+#[derive(serde::Serialize)]
+pub struct Dashboard {
+    active: Option<String>,
+    total_5h: WindowTotal,
+    total_weekly: WindowTotal,
+    /// Most available first; accounts without usage data last.
+    accounts: Vec<AccountRemaining>,
+    /// Where auto-switch would go next (see `autoswitch::best_candidate`).
+    recommended: Option<String>,
+}
+
+impl Dashboard {
+    pub fn build(state: &AppState) -> Self {
+        let now = now_secs();
+        let mut total_5h = WindowTotal::default();
+        let mut total_weekly = WindowTotal::default();
+        let accounts = sort_by_headroom(state.accounts.clone(), &state.usage_cache)
+            .into_iter()
+            .map(|name| {
+                let entry = state.usage_cache.get(&name);
+                let limit_5h = entry.and_then(|e| e.limits.limit_5h.as_ref());
+                let limit_weekly = entry.and_then(|e| e.limits.limit_weekly.as_ref());
+                total_5h.add(limit_5h);
+                total_weekly.add(limit_weekly);
+                AccountRemaining {
+                    title: state.title(&name).to_string(),
+                    active: state.active_account.as_deref() == Some(name.as_str()),
+                    remaining_5h: limit_5h.and_then(UsageWindow::remaining),
+                    remaining_weekly: limit_weekly.and_then(UsageWindow::remaining),
+                    percent_left_5h: limit_5h.map(|w| (100.0 - w.percent()).max(0.0)),
+                    percent_left_weekly: limit_weekly.map(|w| (100.0 - w.percent()).max(0.0)),
+                    next_reset: entry.and_then(|e| e.next_reset(now)),
+                    name,
+                }
+            })
+            .collect();
+        Dashboard {
+            active: state.active_account.clone(),
+            total_5h,
+            total_weekly,
+            accounts,
+            recommended: autoswitch::recommended_next(state),
+        }
+    }
+
+    /// Tray header suffix: " · 62% 5h / 80% W left · next: personal".
+    pub fn header_suffix(&self, state: &AppState) -> String {
+        let mut suffix = String::new();
+        let totals = [
+            ("5h", self.total_5h.percent_remaining()),
+            ("W", self.total_weekly.percent_remaining()),
+        ];
+        let totals: Vec<String> = totals
+            .iter()
+            .filter_map(|(window, percent)| Some(format!("{:.0}% {}", (*percent)?, window)))
+            .collect();
+        if !totals.is_empty() {
+            suffix.push_str(&tr!("tray.quota_left", totals = totals.join(" / ")));
+        }
+        if let Some(next) = &self.recommended {
+            suffix.push_str(&tr!("tray.next", name = state.title(next)));
+        }
+        suffix
+    }
+}
+
+/// Remaining quota summed over all accounts, each account's remaining quota
+/// sorted by availability, and the recommended next account.
+#[tauri::command]
+pub fn get_dashboard(app: AppHandle) -> Dashboard {
+    let mut state = current_state(&app);
+    mock::apply(&app, &mut state);
+    Dashboard::build(&state)
+}
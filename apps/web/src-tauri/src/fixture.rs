@@ -0,0 +1,125 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use std::fs;
+use std::path::Path;
+
+use crate::codex_root;
+use crate::health::jwt_claims;
+
+/// Top-level vault entries copied into a fixture. Everything else (`master.key`,
+/// audit logs, shelved sessions) is left out entirely.
+const FIXTURE_ENTRIES: &[&str] = &[
+    "config.json",
+    "settings.json",
+    "usage_cache.json",
+    "accounts",
+    "backups",
+];
+
+fn is_secret_key(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    key.contains("token") || key.contains("key") || key.contains("secret")
+}
+
+/// Unsigned JWT carrying only the original `exp`, so expiry checks behave the same.
+fn dummy_jwt(original: &str) -> Option<String> {
+    let exp = jwt_claims(original)?["exp"].clone();
+    let header = URL_SAFE_NO_PAD.encode(br#"{"alg":"none","typ":"JWT"}"#);
+    let claims = URL_SAFE_NO_PAD.encode(serde_json::json!({ "exp": exp }).to_string());
+    Some(format!("{}.{}.dummy", header, claims))
+}
+
+/// `secret` carries down from parent keys, so e.g. every value in `team_keys` is replaced.
+fn synthesize(key: &str, value: &serde_json::Value, secret: bool) -> serde_json::Value {
+    let secret = secret || is_secret_key(key);
+    match value {
+        serde_json::Value::Object(map) => serde_json::Value::Object(
+            map.iter()
+                .map(|(k, v)| (k.clone(), synthesize(k, v, secret)))
+                .collect(),
+        ),
+        serde_json::Value::Array(items) => {
+            serde_json::Value::Array(items.iter().map(|v| synthesize(key, v, secret)).collect())
+        }
+        serde_json::Value::String(_) if key.eq_ignore_ascii_case("email") => {
+            "user@example.com".into()
+        }
+        serde_json::Value::String(_) if key.eq_ignore_ascii_case("notes") => "redacted".into(),
+        serde_json::Value::String(s) if secret => dummy_jwt(s)
+            .unwrap_or_else(|| format!("dummy-{}", key.to_ascii_lowercase()))
+            .into(),
+        other => other.clone(),
+    }
+}
+
+fn copy_synthesized(src: &Path, dest: &Path) -> Result<(), String> {
+    if src.is_dir() {
+        fs::create_dir_all(dest)
+            .map_err(|e| format!("Failed to create {}: {}", dest.to_string_lossy(), e))?;
+        let entries = fs::read_dir(src)
+            .map_err(|e| format!("Failed to read {}: {}", src.to_string_lossy(), e))?;
+        for entry in entries.flatten() {
+            copy_synthesized(&entry.path(), &dest.join(entry.file_name()))?;
+        }
+        return Ok(());
+    }
+
+    let name = src.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    let parsed = fs::read_to_string(src)
+        .ok()
+        .and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok());
+
+    let (dest, contents) = match parsed {
+        Some(json) => (
+            dest.to_path_buf(),
+            serde_json::to_string_pretty(&synthesize("", &json, false)).unwrap_or_default(),
+        ),
+        // Encrypted credentials become a plaintext dummy so the account still loads.
+        None if name == "auth.enc" => (
+            dest.with_file_name("auth.json"),
+            serde_json::json!({ "api_key": "dummy-api_key", "type": "api_key" }).to_string(),
+        ),
+        None => (dest.to_path_buf(), "synthetic\n".to_string()),
+    };
+    fs::write(&dest, contents)
+        .map_err(|e| format!("Failed to write {}: {}", dest.to_string_lossy(), e))
+}
+
+/// Debug-only: writes a credential-free copy of the vault to `dest`, loadable
+/// with `CODEX_ACCOUNTS_DIR=<dest>`.
+///
+/// Preserved as-is: directory layout, account and backup names, `config.json`
+/// keys (including `active_account`), settings, and every usage number and
+/// timestamp in `usage_cache.json`.
+///
+/// Synthesized: any string under a key containing `token`, `key` or `secret`
+/// (JWTs keep only their `exp` claim so expiry checks still reproduce),
+/// `email` and `notes` values, `auth.enc` (replaced by a dummy `auth.json`),
+/// and non-JSON files (replaced by a placeholder line).
+///
+/// Dropped: `master.key`, audit logs, shelved sessions and anything else not
+/// listed in `FIXTURE_ENTRIES`.
+#[cfg(debug_assertions)]
+#[tauri::command]
+pub fn export_fixture(dest: String) -> Result<(), String> {
+    let dest = Path::new(&dest);
+    if dest.exists()
+        && fs::read_dir(dest)
+            .map(|mut d| d.next().is_some())
+            .unwrap_or(true)
+    {
+        return Err(format!(
+            "Fixture destination '{}' must be empty",
+            dest.to_string_lossy()
+        ));
+    }
+
+    let root = codex_root();
+    fs::create_dir_all(dest).map_err(|e| format!("Failed to create fixture dir: {}", e))?;
+    for entry in FIXTURE_ENTRIES {
+        let src = root.join(entry);
+        if src.exists() {
+            copy_synthesized(&src, &dest.join(entry))?;
+        }
+    }
+    Ok(())
+}
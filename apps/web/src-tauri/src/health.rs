@@ -0,0 +1,504 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::accounts::account_dir;
+use crate::backup::backups_root;
+use crate::polling::{account_token, token_rejected};
+use crate::secrets::read_plaintext;
+use crate::settings::load_settings;
+use crate::{account_usage, codex_root, current_state, update_tray, AppState};
+
+/// Tokens expiring within this window are reported as "expiring".
+pub const EXPIRY_WARNING_SECS: i64 = 3 * 24 * 60 * 60;
+
+/// How much a single affected account costs the vault score, and the most
+/// that one factor may cost in total. The caps keep one noisy category
+/// (e.g. ten accounts without backups) from zeroing the score on its own.
+struct Weight {
+    per_item: f64,
+    max: f64,
+}
+
+const WEIGHT_BROKEN: Weight = Weight {
+    per_item: 20.0,
+    max: 40.0,
+};
+const WEIGHT_EXPIRING: Weight = Weight {
+    per_item: 10.0,
+    max: 20.0,
+};
+const WEIGHT_MISSING_BACKUP: Weight = Weight {
+    per_item: 5.0,
+    max: 15.0,
+};
+const WEIGHT_OVER_LIMIT: Weight = Weight {
+    per_item: 5.0,
+    max: 10.0,
+};
+const WEIGHT_PERMISSIONS: Weight = Weight {
+    per_item: 5.0,
+    max: 15.0,
+};
+
+pub fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Reads an account's plaintext credentials (from `auth.json` or the keychain).
+/// `Ok(None)` means the account is encrypted (`auth.enc`) and can't be
+/// inspected from here.
+pub fn read_credentials(name: &str) -> Result<Option<serde_json::Value>, String> {
+    let dir = account_dir(name);
+    if let Some(content) = read_plaintext(name)? {
+        return serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| format!("corrupt auth.json: {}", e));
+    }
+    if dir.join("auth.enc").exists() {
+        return Ok(None);
+    }
+    Err("no credential file".to_string())
+}
+
+/// Decodes a JWT's claims without verifying the signature.
+pub fn jwt_claims(token: &str) -> Option<serde_json::Value> {
+    let payload = token.split('.').nth(1)?;
+    let bytes = URL_SAFE_NO_PAD.decode(payload.trim_end_matches('=')).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+fn jwt_expiry(token: &str) -> Option<i64> {
+    jwt_claims(token)?["exp"].as_i64()
+}
+
+/// Best-effort expiry (unix seconds) from either an explicit `expires_at`
+/// or the access token's `exp` claim.
+pub fn token_expiry(creds: &serde_json::Value) -> Option<i64> {
+    let tokens = creds.get("tokens").unwrap_or(creds);
+    if let Some(ts) = tokens["expires_at"].as_f64() {
+        return Some(ts as i64);
+    }
+    ["access_token", "id_token"]
+        .iter()
+        .find_map(|key| tokens[*key].as_str().and_then(jwt_expiry))
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct BrokenAccount {
+    name: String,
+    reason: String,
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct TokenExpiry {
+    name: String,
+    expires_at: i64,
+    expired: bool,
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct PermissionIssue {
+    path: String,
+    mode: String,
+}
+
+fn broken_accounts(state: &AppState) -> Vec<BrokenAccount> {
+    state
+        .accounts
+        .iter()
+        .cloned()
+        .filter_map(|name| match read_credentials(&name) {
+            Ok(_) => None,
+            Err(reason) => Some(BrokenAccount { name, reason }),
+        })
+        .collect()
+}
+
+fn expiring_tokens(state: &AppState) -> Vec<TokenExpiry> {
+    let now = now_secs();
+    state
+        .accounts
+        .iter()
+        .cloned()
+        .filter_map(|name| {
+            let creds = read_credentials(&name).ok()??;
+            let expires_at = token_expiry(&creds)?;
+            (expires_at - now < EXPIRY_WARNING_SECS).then(|| TokenExpiry {
+                name,
+                expires_at,
+                expired: expires_at <= now,
+            })
+        })
+        .collect()
+}
+
+fn missing_backups(state: &AppState) -> Vec<String> {
+    let root = backups_root();
+    state
+        .accounts
+        .iter()
+        .cloned()
+        .filter(|name| {
+            fs::read_dir(root.join(name))
+                .map(|mut entries| entries.next().is_none())
+                .unwrap_or(true)
+        })
+        .collect()
+}
+
+fn over_limit(state: &AppState) -> Vec<String> {
+    state
+        .accounts
+        .iter()
+        .filter(|name| account_usage(state, name).is_some_and(|(p5, pw)| p5.max(pw) >= 100.0))
+        .cloned()
+        .collect()
+}
+
+#[cfg(unix)]
+fn group_or_world_accessible(path: &Path) -> Option<String> {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = fs::metadata(path).ok()?.permissions().mode() & 0o777;
+    (mode & 0o077 != 0).then(|| format!("{:o}", mode))
+}
+
+#[cfg(not(unix))]
+fn group_or_world_accessible(_path: &Path) -> Option<String> {
+    None
+}
+
+fn permission_issues(state: &AppState) -> Vec<PermissionIssue> {
+    let mut candidates = vec![codex_root().join("config.json")];
+    for name in &state.accounts {
+        let dir = account_dir(name);
+        candidates.push(dir.join("auth.json"));
+        candidates.push(dir.join("auth.enc"));
+    }
+
+    candidates
+        .into_iter()
+        .filter(|p| p.exists())
+        .filter_map(|p| {
+            group_or_world_accessible(&p).map(|mode| PermissionIssue {
+                path: p.to_string_lossy().to_string(),
+                mode,
+            })
+        })
+        .collect()
+}
+
+fn collisions(state: &AppState) -> Vec<Vec<String>> {
+    let mut groups: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for name in &state.accounts {
+        groups
+            .entry(name.to_lowercase())
+            .or_default()
+            .push(name.clone());
+    }
+    groups.into_values().filter(|g| g.len() > 1).collect()
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct CacheCoverage {
+    orphan_cache_keys: Vec<String>,
+    accounts_without_usage: Vec<String>,
+}
+
+fn coverage(state: &AppState) -> CacheCoverage {
+    let mut orphan_cache_keys: Vec<String> = state
+        .usage_cache
+        .keys()
+        .filter(|key| !state.accounts.contains(key))
+        .cloned()
+        .collect();
+    orphan_cache_keys.sort();
+
+    let accounts_without_usage = state
+        .accounts
+        .iter()
+        .filter(|name| !state.usage_cache.contains_key(*name))
+        .cloned()
+        .collect();
+
+    CacheCoverage {
+        orphan_cache_keys,
+        accounts_without_usage,
+    }
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct HealthReport {
+    broken: Vec<BrokenAccount>,
+    expiring: Vec<TokenExpiry>,
+    missing_backups: Vec<String>,
+    over_limit: Vec<String>,
+    permission_issues: Vec<PermissionIssue>,
+    case_collisions: Vec<Vec<String>>,
+    orphan_cache_entries: usize,
+    accounts_without_usage: usize,
+}
+
+/// Accounts whose directory has no usable credential file.
+#[tauri::command]
+pub fn check_broken_accounts(app: AppHandle) -> Vec<BrokenAccount> {
+    broken_accounts(&current_state(&app))
+}
+
+/// Accounts whose token has expired or will within `EXPIRY_WARNING_SECS`.
+#[tauri::command]
+pub fn check_expiring_tokens(app: AppHandle) -> Vec<TokenExpiry> {
+    expiring_tokens(&current_state(&app))
+}
+
+/// Accounts with no snapshot under `backups/<name>/`.
+#[tauri::command]
+pub fn check_missing_backups(app: AppHandle) -> Vec<String> {
+    missing_backups(&current_state(&app))
+}
+
+/// Accounts at or past 100% of either their 5h or weekly limit.
+#[tauri::command]
+pub fn check_over_limit(app: AppHandle) -> Vec<String> {
+    over_limit(&current_state(&app))
+}
+
+/// Credential and config files readable by anyone but the owner (unix only).
+#[tauri::command]
+pub fn check_permissions(app: AppHandle) -> Vec<PermissionIssue> {
+    permission_issues(&current_state(&app))
+}
+
+/// Groups of account names that differ only by case and would collide on a
+/// case-insensitive filesystem (macOS default, Windows).
+#[tauri::command]
+pub fn case_collisions(app: AppHandle) -> Vec<Vec<String>> {
+    collisions(&current_state(&app))
+}
+
+/// Usage entries with no matching account, and accounts with no usage entry.
+#[tauri::command]
+pub fn cache_coverage(app: AppHandle) -> CacheCoverage {
+    coverage(&current_state(&app))
+}
+
+fn report(state: &AppState) -> HealthReport {
+    let coverage = coverage(state);
+    HealthReport {
+        broken: broken_accounts(state),
+        expiring: expiring_tokens(state),
+        missing_backups: missing_backups(state),
+        over_limit: over_limit(state),
+        permission_issues: permission_issues(state),
+        case_collisions: collisions(state),
+        orphan_cache_entries: coverage.orphan_cache_keys.len(),
+        accounts_without_usage: coverage.accounts_without_usage.len(),
+    }
+}
+
+/// Runs every individual check in one call.
+#[tauri::command]
+pub fn health_check(app: AppHandle) -> HealthReport {
+    report(&current_state(&app))
+}
+
+#[derive(serde::Serialize)]
+pub struct ScoreFactor {
+    factor: &'static str,
+    count: usize,
+    penalty: f64,
+}
+
+#[derive(serde::Serialize)]
+pub struct VaultScore {
+    score: u8,
+    factors: Vec<ScoreFactor>,
+}
+
+fn factor(name: &'static str, count: usize, weight: &Weight) -> ScoreFactor {
+    ScoreFactor {
+        factor: name,
+        count,
+        penalty: (count as f64 * weight.per_item).min(weight.max),
+    }
+}
+
+/// Folds the `health_check` results into a single 0–100 score.
+#[tauri::command]
+pub fn vault_score(app: AppHandle) -> VaultScore {
+    let report = report(&current_state(&app));
+    let factors = vec![
+        factor("broken_accounts", report.broken.len(), &WEIGHT_BROKEN),
+        factor("expiring_tokens", report.expiring.len(), &WEIGHT_EXPIRING),
+        factor(
+            "missing_backups",
+            report.missing_backups.len(),
+            &WEIGHT_MISSING_BACKUP,
+        ),
+        factor("over_limit", report.over_limit.len(), &WEIGHT_OVER_LIMIT),
+        factor(
+            "permission_issues",
+            report.permission_issues.len(),
+            &WEIGHT_PERMISSIONS,
+        ),
+    ];
+
+    let penalty: f64 = factors.iter().map(|f| f.penalty).sum();
+    VaultScore {
+        score: (100.0 - penalty).clamp(0.0, 100.0).round() as u8,
+        factors,
+    }
+}
+
+#[derive(serde::Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Healthy,
+    /// Expires within `EXPIRY_WARNING_SECS`.
+    Expiring,
+    Expired,
+    /// The API rejected the token (only known when pinging is enabled).
+    Revoked,
+    /// No readable credentials at all.
+    Broken,
+    /// Encrypted (`auth.enc`); can't be inspected without the master key.
+    Unknown,
+}
+
+impl HealthStatus {
+    /// Accounts that would fail if switched to; flagged with ⚠ in the tray.
+    pub fn is_unhealthy(self) -> bool {
+        matches!(
+            self,
+            HealthStatus::Expired | HealthStatus::Revoked | HealthStatus::Broken
+        )
+    }
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct AccountHealth {
+    status: HealthStatus,
+    detail: Option<String>,
+    checked_at: i64,
+}
+
+/// Result of the latest health check per account.
+#[derive(Default)]
+pub struct HealthStatuses(Mutex<BTreeMap<String, AccountHealth>>);
+
+/// The status last recorded for `name`, if it has been checked.
+pub fn status_of<R: Runtime>(app: &AppHandle<R>, name: &str) -> Option<HealthStatus> {
+    let statuses = app.try_state::<HealthStatuses>()?;
+    let statuses = statuses.0.lock().ok()?;
+    statuses.get(name).map(|h| h.status)
+}
+
+/// Offline assessment from the stored credentials alone.
+fn assess(name: &str, now: i64) -> (AccountHealth, Option<String>) {
+    let health = |status, detail: Option<String>| AccountHealth {
+        status,
+        detail,
+        checked_at: now,
+    };
+    let creds = match read_credentials(name) {
+        Ok(Some(creds)) => creds,
+        Ok(None) => return (health(HealthStatus::Unknown, None), None),
+        Err(reason) => return (health(HealthStatus::Broken, Some(reason)), None),
+    };
+    let token = account_token(&creds);
+    let status = match token_expiry(&creds) {
+        Some(expires_at) if expires_at <= now => HealthStatus::Expired,
+        Some(expires_at) if expires_at - now < EXPIRY_WARNING_SECS => HealthStatus::Expiring,
+        _ if token.is_none() => {
+            return (
+                health(HealthStatus::Broken, Some("no token".to_string())),
+                None,
+            )
+        }
+        _ => HealthStatus::Healthy,
+    };
+    (health(status, None), token)
+}
+
+/// Checks every account, pinging the API too when `health_checks.ping` is on.
+async fn check_all(accounts: Vec<String>) -> BTreeMap<String, AccountHealth> {
+    let settings = load_settings();
+    let now = now_secs();
+    let client = if settings.health_checks.ping {
+        reqwest::Client::builder()
+            .timeout(Duration::from_secs(15))
+            .build()
+            .inspect_err(|e| log::warn!("Failed to create HTTP client: {}", e))
+            .ok()
+    } else {
+        None
+    };
+
+    let mut results = BTreeMap::new();
+    for name in accounts {
+        let (mut health, token) = assess(&name, now);
+        if let (Some(client), Some(token)) = (&client, token) {
+            let endpoint = &settings.usage_polling.endpoint;
+            if !health.status.is_unhealthy()
+                && token_rejected(client, endpoint, &token).await == Some(true)
+            {
+                health.status = HealthStatus::Revoked;
+                health.detail = Some("token rejected by the API".to_string());
+            }
+        }
+        results.insert(name, health);
+    }
+    results
+}
+
+/// Runs a check, stores it, and rebuilds the tray if any flag changed.
+async fn refresh_health<R: Runtime>(app: &AppHandle<R>) -> BTreeMap<String, AccountHealth> {
+    let results = check_all(current_state(app).accounts).await;
+    let unhealthy = |map: &BTreeMap<String, AccountHealth>| -> Vec<String> {
+        map.iter()
+            .filter(|(_, h)| h.status.is_unhealthy())
+            .map(|(name, _)| name.clone())
+            .collect()
+    };
+
+    let changed = app
+        .try_state::<HealthStatuses>()
+        .and_then(|statuses| {
+            let mut statuses = statuses.0.lock().ok()?;
+            let changed = unhealthy(&statuses) != unhealthy(&results);
+            *statuses = results.clone();
+            Some(changed)
+        })
+        .unwrap_or(false);
+    if changed {
+        let app_for_closure = app.clone();
+        let _ = app.run_on_main_thread(move || {
+            let _ = update_tray(&app_for_closure);
+        });
+    }
+    results
+}
+
+/// Validates every account's credentials now and returns the per-account status.
+#[tauri::command]
+pub async fn check_account_health(app: AppHandle) -> BTreeMap<String, AccountHealth> {
+    refresh_health(&app).await
+}
+
+/// Checks at launch and then every `health_checks.interval_minutes`.
+pub fn start_health_checker<R: Runtime>(app: AppHandle<R>) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            refresh_health(&app).await;
+            let minutes = load_settings().health_checks.interval_minutes.max(1);
+            tokio::time::sleep(Duration::from_secs(minutes * 60)).await;
+        }
+    });
+}
@@ -0,0 +1,297 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicI64, Ordering};
+use tauri::AppHandle;
+
+use crate::health::now_secs;
+use crate::settings::load_settings;
+use crate::{account_usage, codex_root, current_state, AppState};
+
+/// How far back `usage_trends` looks for the comparison sample.
+const TREND_WINDOW_SECS: i64 = 60 * 60;
+
+const HOUR_SECS: i64 = 60 * 60;
+const DAY_SECS: i64 = 24 * HOUR_SECS;
+
+/// Samples older than this are thinned to one per account per hour...
+const COMPACT_AFTER_SECS: i64 = 7 * DAY_SECS;
+/// ...and dropped entirely past this age.
+const RETAIN_SECS: i64 = 90 * DAY_SECS;
+
+/// When the history was last compacted (unix seconds); 0 = not this session.
+static LAST_COMPACTION: AtomicI64 = AtomicI64::new(0);
+
+/// One point-in-time usage reading, stored one JSON object per line.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct UsageSample {
+    pub ts: i64,
+    pub account: String,
+    pub p5: f64,
+    pub pw: f64,
+}
+
+pub fn history_path() -> PathBuf {
+    codex_root().join("usage_history.jsonl")
+}
+
+/// Appends the current usage of every account that has cache data.
+pub fn record_snapshot(state: &AppState) -> Result<(), String> {
+    let ts = now_secs();
+    let mut lines = String::new();
+    for name in &state.accounts {
+        if let Some((p5, pw)) = account_usage(state, name) {
+            let sample = UsageSample {
+                ts,
+                account: name.clone(),
+                p5,
+                pw,
+            };
+            if let Ok(line) = serde_json::to_string(&sample) {
+                lines.push_str(&line);
+                lines.push('\n');
+            }
+        }
+    }
+    if lines.is_empty() {
+        return Ok(());
+    }
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(history_path())
+        .map_err(|e| format!("Failed to open usage history: {}", e))?;
+    file.write_all(lines.as_bytes())
+        .map_err(|e| format!("Failed to append usage history: {}", e))?;
+
+    if ts - LAST_COMPACTION.load(Ordering::Relaxed) >= DAY_SECS {
+        LAST_COMPACTION.store(ts, Ordering::Relaxed);
+        compact(ts)?;
+    }
+    Ok(())
+}
+
+/// Keeps the newest sample per account per hour for anything older than
+/// `COMPACT_AFTER_SECS`, drops samples older than `RETAIN_SECS`, and rewrites
+/// the file only if that removed something.
+pub fn compact(now: i64) -> Result<(), String> {
+    let samples = read_samples();
+    let before = samples.len();
+
+    let mut kept: Vec<UsageSample> = Vec::with_capacity(before);
+    let mut hourly: BTreeMap<(String, i64), UsageSample> = BTreeMap::new();
+    for sample in samples {
+        let age = now - sample.ts;
+        if age > RETAIN_SECS {
+            continue;
+        }
+        if age > COMPACT_AFTER_SECS {
+            hourly.insert(
+                (sample.account.clone(), sample.ts.div_euclid(HOUR_SECS)),
+                sample,
+            );
+        } else {
+            kept.push(sample);
+        }
+    }
+    kept.extend(hourly.into_values());
+    if kept.len() == before {
+        return Ok(());
+    }
+    kept.sort_by_key(|s| s.ts);
+
+    let mut lines = String::new();
+    for sample in &kept {
+        if let Ok(line) = serde_json::to_string(sample) {
+            lines.push_str(&line);
+            lines.push('\n');
+        }
+    }
+    let path = history_path();
+    let tmp = path.with_extension("jsonl.tmp");
+    fs::write(&tmp, lines).map_err(|e| format!("Failed to write usage history: {}", e))?;
+    fs::rename(&tmp, &path).map_err(|e| format!("Failed to compact usage history: {}", e))
+}
+
+/// All samples, oldest first. Unparseable lines are skipped.
+pub fn read_samples() -> Vec<UsageSample> {
+    let Ok(file) = fs::File::open(history_path()) else {
+        return Vec::new();
+    };
+    BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect()
+}
+
+#[derive(serde::Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum Trend {
+    Up,
+    Down,
+    Flat,
+    Unknown,
+}
+
+impl Trend {
+    pub fn arrow(self) -> &'static str {
+        match self {
+            Trend::Up => "↑",
+            Trend::Down => "↓",
+            Trend::Flat => "→",
+            Trend::Unknown => "",
+        }
+    }
+}
+
+/// Direction of the worst-of-both usage over the last `TREND_WINDOW_SECS`.
+/// Changes smaller than `dead_band` percentage points count as flat.
+pub fn compute_trends(samples: &[UsageSample], dead_band: f64) -> HashMap<String, Trend> {
+    let mut by_account: HashMap<&str, Vec<&UsageSample>> = HashMap::new();
+    for sample in samples {
+        by_account
+            .entry(sample.account.as_str())
+            .or_default()
+            .push(sample);
+    }
+
+    by_account
+        .into_iter()
+        .map(|(account, mut samples)| {
+            samples.sort_by_key(|s| s.ts);
+            let trend = match samples.last() {
+                Some(latest) => samples
+                    .iter()
+                    .rev()
+                    .find(|s| s.ts <= latest.ts - TREND_WINDOW_SECS)
+                    .map(|earlier| {
+                        let delta = latest.p5.max(latest.pw) - earlier.p5.max(earlier.pw);
+                        if delta > dead_band {
+                            Trend::Up
+                        } else if delta < -dead_band {
+                            Trend::Down
+                        } else {
+                            Trend::Flat
+                        }
+                    })
+                    .unwrap_or(Trend::Unknown),
+                None => Trend::Unknown,
+            };
+            (account.to_string(), trend)
+        })
+        .collect()
+}
+
+/// Per-account up/down/flat usage direction over the last hour.
+#[tauri::command]
+pub fn usage_trends(app: AppHandle) -> HashMap<String, Trend> {
+    let mut trends = compute_trends(&read_samples(), load_settings().trend_dead_band);
+    for name in current_state(&app).accounts {
+        trends.entry(name).or_insert(Trend::Unknown);
+    }
+    trends
+}
+
+/// How much history a chart covers, and how coarsely it is bucketed.
+#[derive(serde::Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryRange {
+    /// Last 24 hours in 15-minute buckets.
+    Day,
+    /// Last 7 days in 2-hour buckets.
+    Week,
+    /// Last 30 days in 6-hour buckets.
+    Month,
+}
+
+impl HistoryRange {
+    /// `(span, bucket)` in seconds.
+    fn window(self) -> (i64, i64) {
+        match self {
+            HistoryRange::Day => (DAY_SECS, 15 * 60),
+            HistoryRange::Week => (7 * DAY_SECS, 2 * HOUR_SECS),
+            HistoryRange::Month => (30 * DAY_SECS, 6 * HOUR_SECS),
+        }
+    }
+}
+
+/// Peak usage within one time bucket.
+#[derive(serde::Serialize, Clone)]
+pub struct UsageBucket {
+    /// Start of the bucket (unix seconds).
+    ts: i64,
+    p5: f64,
+    pw: f64,
+    samples: usize,
+}
+
+/// Buckets `account`'s samples from the last `range`, oldest first. Empty
+/// buckets are omitted.
+fn bucketed(
+    samples: &[UsageSample],
+    account: &str,
+    range: HistoryRange,
+    now: i64,
+) -> Vec<UsageBucket> {
+    let (span, bucket) = range.window();
+    let mut buckets: BTreeMap<i64, UsageBucket> = BTreeMap::new();
+    for sample in samples
+        .iter()
+        .filter(|s| s.account == account && s.ts > now - span)
+    {
+        let ts = sample.ts - sample.ts.rem_euclid(bucket);
+        let entry = buckets.entry(ts).or_insert(UsageBucket {
+            ts,
+            p5: 0.0,
+            pw: 0.0,
+            samples: 0,
+        });
+        entry.p5 = entry.p5.max(sample.p5);
+        entry.pw = entry.pw.max(sample.pw);
+        entry.samples += 1;
+    }
+    buckets.into_values().collect()
+}
+
+/// `account`'s usage over `range`, bucketed for charting.
+#[tauri::command]
+pub fn get_usage_history(account: String, range: HistoryRange) -> Vec<UsageBucket> {
+    bucketed(&read_samples(), &account, range, now_secs())
+}
+
+#[derive(serde::Serialize)]
+pub struct AccountHistorySummary {
+    account: String,
+    /// Most recent sample, if any.
+    latest: Option<(f64, f64)>,
+    /// Highest 5h and weekly usage seen over the last 7 days.
+    peak_p5_week: f64,
+    peak_pw_week: f64,
+    /// The last 24 hours, bucketed as for `HistoryRange::Day`.
+    day: Vec<UsageBucket>,
+}
+
+/// One chart-ready summary per account, in vault order.
+#[tauri::command]
+pub fn get_usage_summary(app: AppHandle) -> Vec<AccountHistorySummary> {
+    let samples = read_samples();
+    let now = now_secs();
+    current_state(&app)
+        .accounts
+        .into_iter()
+        .map(|account| {
+            let own: Vec<&UsageSample> = samples.iter().filter(|s| s.account == account).collect();
+            let week: Vec<&&UsageSample> =
+                own.iter().filter(|s| s.ts > now - 7 * DAY_SECS).collect();
+            AccountHistorySummary {
+                latest: own.last().map(|s| (s.p5, s.pw)),
+                peak_p5_week: week.iter().map(|s| s.p5).fold(0.0, f64::max),
+                peak_pw_week: week.iter().map(|s| s.pw).fold(0.0, f64::max),
+                day: bucketed(&samples, &account, HistoryRange::Day, now),
+                account,
+            }
+        })
+        .collect()
+}
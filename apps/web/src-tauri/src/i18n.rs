@@ -0,0 +1,179 @@
+//! Translations for the tray, notifications and user-facing errors.
+//!
+//! Catalogs are flat JSON objects bundled from `locales/`, mapping a key to
+//! text with `{name}` placeholders. A key missing from the current locale
+//! falls back to English, then to the key itself. The locale comes from the
+//! `locale` setting; `"auto"` follows the OS.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::{OnceLock, RwLock};
+
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// The `locale` setting value that follows the OS.
+pub const AUTO_LOCALE: &str = "auto";
+
+const BUNDLED: [(&str, &str); 3] = [
+    ("en", include_str!("../locales/en.json")),
+    ("ru", include_str!("../locales/ru.json")),
+    ("de", include_str!("../locales/de.json")),
+];
+
+type Catalog = HashMap<String, String>;
+
+static CATALOGS: OnceLock<HashMap<&'static str, Catalog>> = OnceLock::new();
+static CURRENT: RwLock<&'static str> = RwLock::new(DEFAULT_LOCALE);
+
+fn catalogs() -> &'static HashMap<&'static str, Catalog> {
+    CATALOGS.get_or_init(|| {
+        BUNDLED
+            .iter()
+            .map(|(code, json)| {
+                let catalog = serde_json::from_str(json).unwrap_or_else(|e| {
+                    log::error!("Bundled locale '{}' is invalid: {}", code, e);
+                    Catalog::new()
+                });
+                (*code, catalog)
+            })
+            .collect()
+    })
+}
+
+/// Bundled locale codes, English first.
+pub fn available() -> Vec<&'static str> {
+    BUNDLED.iter().map(|(code, _)| *code).collect()
+}
+
+/// The bundled locale for a tag like `de-AT` or `ru_RU.UTF-8`, if any.
+fn bundled(tag: &str) -> Option<&'static str> {
+    let language = tag
+        .split(['-', '_', '.'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    BUNDLED
+        .iter()
+        .map(|(code, _)| *code)
+        .find(|code| *code == language)
+}
+
+/// The locale `setting` selects: a bundled code as is, `"auto"` (or anything
+/// unknown) the OS language when bundled, else English.
+pub fn resolve(setting: &str) -> &'static str {
+    if setting != AUTO_LOCALE {
+        if let Some(code) = bundled(setting) {
+            return code;
+        }
+    }
+    sys_locale::get_locale()
+        .as_deref()
+        .and_then(bundled)
+        .unwrap_or(DEFAULT_LOCALE)
+}
+
+/// Switches every later `t`/`tr!` to the locale `setting` selects.
+pub fn set_locale(setting: &str) {
+    let code = resolve(setting);
+    if let Ok(mut current) = CURRENT.write() {
+        *current = code;
+    }
+}
+
+pub fn current() -> &'static str {
+    CURRENT.read().map(|c| *c).unwrap_or(DEFAULT_LOCALE)
+}
+
+/// The text for `key` in the current locale.
+pub fn t(key: &str) -> String {
+    let catalogs = catalogs();
+    [current(), DEFAULT_LOCALE]
+        .iter()
+        .find_map(|code| catalogs.get(code)?.get(key))
+        .cloned()
+        .unwrap_or_else(|| key.to_string())
+}
+
+/// `t(key)` with each `{name}` replaced by its value from `args`. The text
+/// is scanned once, so braces inside a value are never substituted, and
+/// placeholders without a value are left as they are.
+pub fn format(key: &str, args: &[(&str, &dyn Display)]) -> String {
+    let template = t(key);
+    let mut text = String::with_capacity(template.len());
+    let mut rest = template.as_str();
+    while let Some(open) = rest.find('{') {
+        text.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let arg = after.find('}').and_then(|close| {
+            let (_, value) = args.iter().find(|(name, _)| *name == &after[..close])?;
+            Some((close, value))
+        });
+        match arg {
+            Some((close, value)) => {
+                text.push_str(&value.to_string());
+                rest = &after[close + 1..];
+            }
+            None => {
+                text.push('{');
+                rest = after;
+            }
+        }
+    }
+    text.push_str(rest);
+    text
+}
+
+/// `tr!("key")` or `tr!("key", name = value, ...)`.
+macro_rules! tr {
+    ($key:expr) => {
+        $crate::i18n::t($key)
+    };
+    ($key:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::format(
+            $key,
+            &[$((stringify!($name), &$value as &dyn std::fmt::Display)),+],
+        )
+    };
+}
+pub(crate) use tr;
+
+/// A limit's name ("5h", "weekly") as shown to the user.
+pub fn limit_name(limit: &str) -> String {
+    t(&format!("limit.{}", limit))
+}
+
+#[derive(serde::Serialize)]
+pub struct LocaleInfo {
+    /// The `locale` setting: a code or `"auto"`.
+    setting: String,
+    /// The locale actually in use.
+    current: &'static str,
+    available: Vec<&'static str>,
+}
+
+#[tauri::command]
+pub fn get_locale() -> LocaleInfo {
+    LocaleInfo {
+        setting: crate::settings::load_settings().locale,
+        current: current(),
+        available: available(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Keys missing from every catalog format as themselves.
+
+    #[test]
+    fn values_are_not_substituted_again() {
+        assert_eq!(
+            tr!("{name}: {error}", name = "{error}", error = "boom"),
+            "{error}: boom"
+        );
+    }
+
+    #[test]
+    fn unknown_and_unclosed_placeholders_are_kept() {
+        assert_eq!(tr!("{name} {missing} {", name = "work"), "work {missing} {");
+    }
+}
@@ -0,0 +1,203 @@
+//! File-based control channel for the companion CLI.
+//!
+//! The CLI writes `~/.codex-accounts/.ipc/command.json`:
+//!
+//! ```json
+//! { "id": "any-string", "action": "switch", "account": "work" }
+//! { "id": "any-string", "action": "refresh" }
+//! { "id": "any-string", "action": "cwd", "path": "/home/me/src/work-repo" }
+//! ```
+//!
+//! `cwd` switches to the account mapped to that directory (see `projects.rs`),
+//! if any. Either switch waits for a running Codex session to end, so
+//! `active_account` in the result is still the old one until then.
+//!
+//! The app runs the action through its normal in-app path, deletes the
+//! command file and writes `.ipc/result.json`:
+//!
+//! ```json
+//! { "id": "any-string", "ok": true, "error": null, "active_account": "work" }
+//! ```
+//!
+//! The `.ipc` directory is created `0700`, and on unix a command file that is
+//! not owned by the directory's owner, or is group/world writable, is ignored.
+
+use notify::{Config as NotifyConfig, RecommendedWatcher, RecursiveMode, Watcher};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::channel;
+use tauri::{AppHandle, Emitter, Runtime};
+
+use crate::accounts::write_private;
+use crate::session::{self, SessionPolicy};
+use crate::{codex_root, load_state, projects, update_tray};
+
+const COMMAND_FILE: &str = "command.json";
+const RESULT_FILE: &str = "result.json";
+
+#[derive(serde::Deserialize)]
+#[serde(tag = "action", rename_all = "lowercase")]
+enum IpcAction {
+    Switch { account: String },
+    Refresh,
+    Cwd { path: String },
+}
+
+#[derive(serde::Deserialize)]
+struct IpcCommand {
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(flatten)]
+    action: IpcAction,
+}
+
+#[derive(serde::Serialize)]
+struct IpcResult {
+    id: Option<String>,
+    ok: bool,
+    error: Option<String>,
+    active_account: Option<String>,
+}
+
+pub fn ipc_dir() -> PathBuf {
+    codex_root().join(".ipc")
+}
+
+fn ensure_ipc_dir() -> Result<PathBuf, String> {
+    let dir = ipc_dir();
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create IPC dir: {}", e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o700))
+            .map_err(|e| format!("Failed to secure IPC dir: {}", e))?;
+    }
+    Ok(dir)
+}
+
+/// The file must be owned by whoever owns the (0700) IPC dir, i.e. us.
+#[cfg(unix)]
+fn is_trusted(dir: &Path, path: &Path) -> bool {
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+    let (Ok(dir_meta), Ok(meta)) = (fs::metadata(dir), fs::symlink_metadata(path)) else {
+        return false;
+    };
+    meta.file_type().is_file()
+        && meta.uid() == dir_meta.uid()
+        && meta.permissions().mode() & 0o022 == 0
+}
+
+#[cfg(not(unix))]
+fn is_trusted(_dir: &Path, path: &Path) -> bool {
+    path.is_file()
+}
+
+fn execute<R: Runtime>(app: &AppHandle<R>, command: IpcCommand) -> IpcResult {
+    let outcome = match &command.action {
+        IpcAction::Switch { account } => session::switch(app, account, SessionPolicy::Queue)
+            .map(|_| ())
+            .map_err(String::from),
+        IpcAction::Refresh => Ok(()),
+        IpcAction::Cwd { path } => projects::switch_for_cwd(app, path).map(|_| ()),
+    };
+
+    if outcome.is_ok() {
+        let _ = app.emit("ipc-command-executed", ());
+        let app_for_closure = app.clone();
+        let _ = app.run_on_main_thread(move || {
+            let _ = update_tray(&app_for_closure);
+        });
+    }
+
+    IpcResult {
+        id: command.id,
+        ok: outcome.is_ok(),
+        error: outcome.err(),
+        active_account: load_state().active_account,
+    }
+}
+
+fn process_command_file<R: Runtime>(app: &AppHandle<R>, dir: &Path) {
+    let path = dir.join(COMMAND_FILE);
+    if !path.exists() {
+        return;
+    }
+    if !is_trusted(dir, &path) {
+        log::warn!(
+            "Ignoring untrusted IPC command file {}",
+            path.to_string_lossy()
+        );
+        return;
+    }
+
+    let content = fs::read_to_string(&path);
+    let _ = fs::remove_file(&path);
+
+    let result = match content
+        .map_err(|e| e.to_string())
+        .and_then(|c| serde_json::from_str::<IpcCommand>(&c).map_err(|e| e.to_string()))
+    {
+        Ok(command) => execute(app, command),
+        Err(e) => IpcResult {
+            id: None,
+            ok: false,
+            error: Some(format!("Invalid IPC command: {}", e)),
+            active_account: None,
+        },
+    };
+
+    match serde_json::to_vec_pretty(&result) {
+        Ok(body) => {
+            if let Err(e) = write_private(&dir.join(RESULT_FILE), &body) {
+                log::warn!("Failed to write IPC result: {}", e);
+            }
+        }
+        Err(e) => log::warn!("Failed to serialize IPC result: {}", e),
+    }
+}
+
+/// Watches `.ipc/` and executes command files as they appear.
+pub fn start_ipc_watcher<R: Runtime>(app: AppHandle<R>) {
+    std::thread::spawn(move || {
+        let dir = match ensure_ipc_dir() {
+            Ok(dir) => dir,
+            Err(e) => {
+                log::warn!("{}", e);
+                return;
+            }
+        };
+
+        let (tx, rx) = channel();
+        let mut watcher = match RecommendedWatcher::new(tx, NotifyConfig::default()) {
+            Ok(w) => w,
+            Err(e) => {
+                log::warn!("Failed to create IPC watcher: {:?}", e);
+                return;
+            }
+        };
+        if let Err(e) = watcher.watch(&dir, RecursiveMode::NonRecursive) {
+            log::warn!("Failed to watch IPC dir: {:?}", e);
+            return;
+        }
+
+        // A command may have been left while the app wasn't running.
+        process_command_file(&app, &dir);
+
+        loop {
+            match rx.recv() {
+                Ok(Ok(event)) => {
+                    if (event.kind.is_modify() || event.kind.is_create())
+                        && event
+                            .paths
+                            .iter()
+                            .any(|p| p.file_name().is_some_and(|n| n == COMMAND_FILE))
+                    {
+                        process_command_file(&app, &dir);
+                    }
+                }
+                Ok(Err(e)) => log::warn!("IPC watch error: {:?}", e),
+                Err(_) => break,
+            }
+        }
+    });
+}
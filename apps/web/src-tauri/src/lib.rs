@@ -1,48 +1,266 @@
 use notify::{Config as NotifyConfig, RecommendedWatcher, RecursiveMode, Watcher};
 use std::collections::HashMap;
-use std::env;
 use std::fs;
-use std::path::PathBuf;
-use std::sync::mpsc::channel;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use tauri::{
-    menu::{CheckMenuItem, Menu, MenuItem},
-    tray::{MouseButton, TrayIconBuilder, TrayIconEvent},
-    AppHandle, Emitter, Manager, Runtime,
+    menu::{CheckMenuItem, Menu, MenuItem, Submenu},
+    tray::{MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent},
+    AppHandle, Emitter, Manager, RunEvent, Runtime,
 };
 
+mod accounts;
+mod alerts;
+mod archive;
+mod autostart;
+mod autoswitch;
+mod backup;
+mod cli;
+mod dashboard;
+#[cfg(debug_assertions)]
+mod fixture;
+mod health;
+mod history;
+mod i18n;
+mod ipc;
+mod lifecycle;
+mod logins;
+mod logs;
+mod metadata;
+#[cfg(debug_assertions)]
+mod mock;
+/// Release builds have no usage mocks; `apply` leaves the state as loaded.
+#[cfg(not(debug_assertions))]
+mod mock {
+    pub fn apply<R: tauri::Runtime>(_app: &tauri::AppHandle<R>, _state: &mut crate::AppState) {}
+}
+mod oauth;
+mod overrides;
+mod paths;
+mod polling;
+mod process;
+mod projects;
+mod recovery;
+mod resets;
+mod rotation;
+mod schedule;
+mod secrets;
+mod session;
+mod settings;
+mod sync;
+mod transfer;
+mod tray_icon;
+mod usage;
+
+use i18n::tr;
+use paths::codex_root;
+
 #[derive(serde::Deserialize)]
 struct Config {
     active_account: Option<String>,
+    /// `"name"` (default) or `"usage"`; anything else falls back to `"name"`.
+    #[serde(default)]
+    tray_sort: Option<String>,
 }
 
-#[derive(serde::Deserialize)]
+/// Account order in the tray, from `config.json`'s `tray_sort`.
+#[derive(Clone, Copy, PartialEq)]
+enum TraySort {
+    Name,
+    /// Most headroom first; accounts without usage data last.
+    Usage,
+}
+
+/// Compact per-account usage (see `usage.rs`). `raw` is the original cache
+/// blob, served by `get_raw_usage` and dropped for non-active accounts once
+/// the cache grows past its cap.
+#[derive(Clone)]
 struct CacheEntry {
-    limits: serde_json::Value, // We just need to parse usage
+    limits: usage::UsageLimits,
+    /// Unix seconds the CLI last refreshed this entry, if it said.
+    updated_at: Option<i64>,
+    raw: Option<serde_json::Value>,
+}
+
+/// A vault file that exists but couldn't be used; its data is treated as absent.
+#[derive(serde::Serialize, Clone)]
+struct LoadWarning {
+    file: String,
+    error: String,
 }
 
+#[derive(Clone)]
 struct AppState {
     active_account: Option<String>,
+    tray_sort: TraySort,
+    /// Pinned accounts first, each part in `tray_sort` order.
     accounts: Vec<String>,
+    /// Only accounts that have a `meta.json`.
+    metadata: HashMap<String, metadata::AccountMetadata>,
     usage_cache: HashMap<String, CacheEntry>,
+    warnings: Vec<LoadWarning>,
 }
 
-fn load_state() -> AppState {
-    let home = env::var("HOME").unwrap_or_default();
-    let root = PathBuf::from(home).join(".codex-accounts");
-
-    // 1. Get active
-    let config_path = root.join("config.json");
-    let mut active_account = None;
-    if let Ok(content) = fs::read_to_string(&config_path) {
-        if let Ok(json) = serde_json::from_str::<Config>(&content) {
-            active_account = json.active_account;
+/// Usage (in percent of either limit) at which a tray entry turns red and
+/// gets a warning sign, and at which it turns yellow.
+const USAGE_CRITICAL_PERCENT: f64 = 90.0;
+const USAGE_WARNING_PERCENT: f64 = 70.0;
+
+/// 5h and weekly usage as percentages of their limits.
+fn usage_percentages(entry: &CacheEntry) -> (f64, f64) {
+    let p5 = entry.limits.limit_5h.clone().unwrap_or_default().percent();
+    let pw = entry
+        .limits
+        .limit_weekly
+        .clone()
+        .unwrap_or_default()
+        .percent();
+    (p5, pw)
+}
+
+/// The higher of the windows actually present in `entry`; `None` when it has neither.
+fn worst_percent(entry: &CacheEntry) -> Option<f64> {
+    [&entry.limits.limit_5h, &entry.limits.limit_weekly]
+        .into_iter()
+        .flatten()
+        .map(|w| w.percent())
+        .reduce(f64::max)
+}
+
+#[derive(Clone, Copy)]
+enum UsageLevel {
+    Low,
+    Warning,
+    Critical,
+}
+
+impl UsageLevel {
+    fn of(percent: f64) -> Self {
+        if percent >= USAGE_CRITICAL_PERCENT {
+            UsageLevel::Critical
+        } else if percent >= USAGE_WARNING_PERCENT {
+            UsageLevel::Warning
+        } else {
+            UsageLevel::Low
+        }
+    }
+
+    fn rgb(self) -> [u8; 3] {
+        match self {
+            UsageLevel::Low => [0x34, 0xc7, 0x59],
+            UsageLevel::Warning => [0xff, 0xcc, 0x00],
+            UsageLevel::Critical => [0xff, 0x3b, 0x30],
+        }
+    }
+}
+
+/// Status dot for `worst_percent(entry)`.
+fn urgency_badge(entry: &CacheEntry) -> Option<&'static str> {
+    Some(match UsageLevel::of(worst_percent(entry)?) {
+        UsageLevel::Critical => "⚠️ 🔴",
+        UsageLevel::Warning => "🟡",
+        UsageLevel::Low => "🟢",
+    })
+}
+
+/// Orders `accounts` least-used first by `worst_percent`; accounts without
+/// usage data follow, alphabetically.
+fn sort_by_headroom(
+    mut accounts: Vec<String>,
+    usage_cache: &HashMap<String, CacheEntry>,
+) -> Vec<String> {
+    accounts.sort_by(|a, b| {
+        let pa = usage_cache.get(a).and_then(worst_percent);
+        let pb = usage_cache.get(b).and_then(worst_percent);
+        match (pa, pb) {
+            (Some(pa), Some(pb)) => pa.total_cmp(&pb).then_with(|| a.cmp(b)),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => a.cmp(b),
+        }
+    });
+    accounts
+}
+
+/// `usage_percentages` for `name`, or `None` when it has no cache entry.
+fn account_usage(state: &AppState, name: &str) -> Option<(f64, f64)> {
+    state.usage_cache.get(name).map(usage_percentages)
+}
+
+#[derive(serde::Serialize)]
+struct AccountSummary {
+    name: String,
+    usage_5h: Option<f64>,
+    usage_weekly: Option<f64>,
+}
+
+impl AccountSummary {
+    fn new(state: &AppState, name: &str) -> Self {
+        let usage = account_usage(state, name);
+        AccountSummary {
+            name: name.to_string(),
+            usage_5h: usage.map(|(p5, _)| p5),
+            usage_weekly: usage.map(|(_, pw)| pw),
         }
     }
+}
+
+#[derive(serde::Serialize)]
+struct AccountsState {
+    active_account: Option<String>,
+    accounts: Vec<AccountSummary>,
+}
+
+/// The same view of the vault the tray is built from, for the webview.
+#[tauri::command]
+fn get_accounts_state(app: AppHandle) -> AccountsState {
+    let state = current_state(&app);
+    let accounts = state
+        .accounts
+        .iter()
+        .map(|name| AccountSummary::new(&state, name))
+        .collect();
+    AccountsState {
+        active_account: state.active_account.clone(),
+        accounts,
+    }
+}
 
-    // 2. Get accounts (Personal Vault)
-    let accounts_dir = root.join("accounts");
+/// Shows a native notification; failures are logged, never fatal.
+fn notify<R: Runtime>(app: &AppHandle<R>, title: &str, body: &str) {
+    use tauri_plugin_notification::NotificationExt;
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        log::warn!("Failed to show notification: {}", e);
+    }
+}
+
+/// Reads `config.json`: the active account and the tray order.
+fn read_config(warnings: &mut Vec<LoadWarning>) -> (Option<String>, TraySort) {
+    match read_json_file::<Config>(&codex_root().join("config.json"), warnings) {
+        Some(json) => {
+            let tray_sort = if json.tray_sort.as_deref() == Some("usage") {
+                TraySort::Usage
+            } else {
+                TraySort::Name
+            };
+            (json.active_account, tray_sort)
+        }
+        None => (None, TraySort::Name),
+    }
+}
+
+/// Account directories in the vault, alphabetically.
+fn read_accounts() -> Vec<String> {
+    accounts_under(&codex_root())
+}
+
+/// Account directories under `root`'s `accounts/`; none if it doesn't exist.
+fn accounts_under(root: &Path) -> Vec<String> {
     let mut accounts = Vec::new();
-    if let Ok(entries) = fs::read_dir(accounts_dir) {
+    if let Ok(entries) = fs::read_dir(root.join("accounts")) {
         for entry in entries.flatten() {
             if entry.path().is_dir() {
                 if let Some(name) = entry.file_name().to_str() {
@@ -54,67 +272,252 @@ fn load_state() -> AppState {
         }
     }
     accounts.sort();
+    accounts
+}
 
-    // 3. Load Usage Cache
-    let cache_path = root.join("usage_cache.json");
-    let mut usage_cache = HashMap::new();
-    if let Ok(content) = fs::read_to_string(&cache_path) {
-        if let Ok(parsed) = serde_json::from_str::<HashMap<String, CacheEntry>>(&content) {
-            usage_cache = parsed;
-        }
-    }
+/// Parses `usage_cache.json` into compact entries.
+fn read_usage_cache(warnings: &mut Vec<LoadWarning>) -> HashMap<String, CacheEntry> {
+    read_json_file::<HashMap<String, serde_json::Value>>(
+        &codex_root().join("usage_cache.json"),
+        warnings,
+    )
+    .map(|parsed| {
+        parsed
+            .into_iter()
+            .map(|(name, raw)| (name, CacheEntry::from_raw(raw)))
+            .collect()
+    })
+    .unwrap_or_default()
+}
 
-    AppState {
+/// Reads the whole vault from disk. Code with an `AppHandle` should prefer
+/// `current_state`, which doesn't touch the disk.
+fn load_state() -> AppState {
+    let mut warnings = Vec::new();
+    let (active_account, tray_sort) = read_config(&mut warnings);
+    let accounts = read_accounts();
+    let mut state = AppState {
         active_account,
+        tray_sort,
+        metadata: metadata::load_all(&accounts),
         accounts,
-        usage_cache,
+        usage_cache: read_usage_cache(&mut warnings),
+        warnings,
+    };
+    state.settle();
+    state
+}
+
+impl AppState {
+    /// Re-reads only the files `changes` covers. Warnings for those files are
+    /// replaced; the rest are kept.
+    fn refresh(&mut self, changes: &PendingChanges) {
+        let mut warnings = Vec::new();
+        if changes.config {
+            (self.active_account, self.tray_sort) = read_config(&mut warnings);
+        }
+        if changes.accounts {
+            self.accounts = read_accounts();
+            self.metadata = metadata::load_all(&self.accounts);
+        }
+        if changes.usage {
+            self.usage_cache = read_usage_cache(&mut warnings);
+        }
+        self.warnings.retain(|w| {
+            !(changes.config && w.file == "config.json"
+                || changes.usage && w.file == "usage_cache.json")
+        });
+        self.warnings.extend(warnings);
+        self.settle();
+    }
+
+    /// Applies the raw-blob cap and the tray order after any reload.
+    fn settle(&mut self) {
+        usage::enforce_raw_cap(&mut self.usage_cache, self.active_account.as_deref());
+        let accounts = std::mem::take(&mut self.accounts);
+        let mut accounts = match self.tray_sort {
+            TraySort::Usage => sort_by_headroom(accounts, &self.usage_cache),
+            TraySort::Name => {
+                let mut accounts = accounts;
+                accounts.sort();
+                accounts
+            }
+        };
+        // Stable, so both groups keep the order above.
+        accounts.sort_by_key(|name| !self.is_pinned(name));
+        self.accounts = accounts;
+    }
+
+    fn is_pinned(&self, name: &str) -> bool {
+        self.metadata.get(name).is_some_and(|m| m.pinned)
+    }
+
+    /// The display name from `meta.json`, or the account name.
+    fn title<'a>(&'a self, name: &'a str) -> &'a str {
+        self.metadata.get(name).map_or(name, |m| m.title(name))
     }
 }
 
-fn build_tray_menu<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<Menu<R>> {
+/// The vault as last read, shared by the tray, commands and watcher.
+struct SharedState(Mutex<AppState>);
+
+/// A copy of the shared state; falls back to reading the disk before the
+/// state is managed.
+fn current_state<R: Runtime>(app: &AppHandle<R>) -> AppState {
+    app.try_state::<SharedState>()
+        .and_then(|shared| shared.0.lock().ok().map(|state| state.clone()))
+        .unwrap_or_else(load_state)
+}
+
+/// Replaces the shared state with a full read of the vault. Used after the
+/// app changes the vault itself, since those changes pause the watcher.
+fn reload_state<R: Runtime>(app: &AppHandle<R>) {
     let state = load_state();
-    let active = state.active_account.unwrap_or_default();
+    if let Some(shared) = app.try_state::<SharedState>() {
+        if let Ok(mut current) = shared.0.lock() {
+            *current = state;
+        }
+    }
+}
+
+/// Re-reads the files the watcher saw change and returns the updated state.
+fn refresh_state<R: Runtime>(app: &AppHandle<R>, changes: &PendingChanges) -> AppState {
+    let Some(shared) = app.try_state::<SharedState>() else {
+        return load_state();
+    };
+    let Ok(mut state) = shared.0.lock() else {
+        return load_state();
+    };
+    state.refresh(changes);
+    state.clone()
+}
+
+/// Reads and parses `path`. A missing file is simply `None`; one that exists
+/// but can't be read or parsed is also `None`, plus a warning.
+fn read_json_file<T: serde::de::DeserializeOwned>(
+    path: &Path,
+    warnings: &mut Vec<LoadWarning>,
+) -> Option<T> {
+    let warn = |error: String| LoadWarning {
+        file: path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        error,
+    };
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+        Err(e) => {
+            warnings.push(warn(e.to_string()));
+            return None;
+        }
+    };
+    serde_json::from_str(&content)
+        .map_err(|e| warnings.push(warn(e.to_string())))
+        .ok()
+}
 
-    let open_i = MenuItem::with_id(app, "open", "Open Manager", true, None::<&str>)?;
-    let add_i = MenuItem::with_id(app, "add", "Add Account...", true, None::<&str>)?;
-    let quit_i = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+/// Logs load warnings and forwards each to the webview as `state-load-warning`.
+fn report_load_warnings<R: Runtime>(app: &AppHandle<R>, state: &AppState) {
+    for warning in &state.warnings {
+        log::warn!("Failed to load {}: {}", warning.file, warning.error);
+        let _ = app.emit("state-load-warning", warning);
+    }
+}
+
+fn build_tray_menu<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<Menu<R>> {
+    let mut state = current_state(app);
+    mock::apply(app, &mut state);
+    let settings = settings::load_settings();
+    let temporary = overrides::active_override(app);
+    let active = temporary
+        .clone()
+        .or(state.active_account.clone())
+        .unwrap_or_default();
+
+    // Pinning only reorders; the item keeps its `switch:{name}` id and checkmark.
+    if settings.pin_active_to_top {
+        if let Some(pos) = state.accounts.iter().position(|a| *a == active) {
+            let pinned = state.accounts.remove(pos);
+            state.accounts.insert(0, pinned);
+        }
+    }
+
+    let open_i = MenuItem::with_id(app, "open", tr!("tray.open"), true, None::<&str>)?;
+    let add_i = MenuItem::with_id(app, "add", tr!("tray.add"), true, None::<&str>)?;
+    let quit_i = MenuItem::with_id(app, "quit", tr!("tray.quit"), true, None::<&str>)?;
     let sep = tauri::menu::PredefinedMenuItem::separator(app)?;
 
     let menu = Menu::with_items(app, &[&open_i, &sep])?;
 
+    let trends = if settings.show_usage_trends {
+        history::compute_trends(&history::read_samples(), settings.trend_dead_band)
+    } else {
+        HashMap::new()
+    };
+
+    let now = health::now_secs();
+    let stale_after = settings.stale_after_minutes as i64 * 60;
+
     // Accounts Section
     let count = state.accounts.len();
     if count > 0 {
-        let header_title = if active.is_empty() {
-            format!("Accounts ({})", count)
+        let active_title = state.title(&active);
+        let mut header_title = if active.is_empty() {
+            tr!("tray.accounts", count = count)
+        } else if temporary.is_some() {
+            tr!("tray.active_temporary", name = active_title, count = count)
         } else {
-            format!("Active: {} ({})", active, count)
+            tr!("tray.active", name = active_title, count = count)
         };
+        header_title.push_str(&dashboard::Dashboard::build(&state).header_suffix(&state));
 
         let header = MenuItem::with_id(app, "disabled", header_title, false, None::<&str>)?;
         menu.append(&header)?;
         menu.append(&add_i)?; // "Add Account" near the list
         menu.append(&sep)?;
 
-        for name in state.accounts {
-            let is_active = name == active;
-            let mut label = name.clone();
+        for name in &state.accounts {
+            let is_active = *name == active;
+            let title = state.title(name);
+            let mut label = title.to_string();
 
             // Format Usage Stats
-            if let Some(entry) = state.usage_cache.get(&name) {
-                // Parse safely using serde_json::Value
-                let l5 = &entry.limits["limit_5h"];
-                let lw = &entry.limits["limit_weekly"];
-
-                let u5 = l5["used"].as_f64().unwrap_or(0.0);
-                let m5 = l5["limit"].as_f64().unwrap_or(1.0);
-                let p5 = (u5 / m5) * 100.0;
-
-                let uw = lw["used"].as_f64().unwrap_or(0.0);
-                let mw = lw["limit"].as_f64().unwrap_or(1.0);
-                let pw = (uw / mw) * 100.0;
-
-                label = format!("{} [5h: {:.0}% / W: {:.0}%]", name, p5, pw);
+            if let Some((p5, pw)) = account_usage(&state, name) {
+                label = tr!(
+                    "tray.usage",
+                    name = title,
+                    p5 = format!("{:.0}", p5),
+                    pw = format!("{:.0}", pw),
+                );
+            }
+            if let Some(badge) = state.usage_cache.get(name).and_then(urgency_badge) {
+                label = format!("{} {}", badge, label);
+            }
+            if health::status_of(app, name).is_some_and(|s| s.is_unhealthy()) {
+                label = format!("⚠ {}", label);
+            }
+            let emoji = state.metadata.get(name).and_then(|m| m.emoji.as_ref());
+            if let Some(prefix) = emoji.or(settings.labels.get(name)) {
+                label = format!("{} {}", prefix, label);
+            }
+            if let Some(trend) = trends.get(name).filter(|t| **t != history::Trend::Unknown) {
+                label = format!("{} {}", label, trend.arrow());
+            }
+            if let Some(at) = state.usage_cache.get(name).and_then(|e| e.next_reset(now)) {
+                label = tr!(
+                    "tray.resets_in",
+                    label = label,
+                    countdown = resets::format_countdown(now, at),
+                );
+            }
+            if state
+                .usage_cache
+                .get(name)
+                .is_some_and(|entry| entry.is_stale(now, stale_after))
+            {
+                label = tr!("tray.stale", label = label);
             }
 
             let id = format!("switch:{}", name);
@@ -129,137 +532,858 @@ fn build_tray_menu<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<Menu<R>> {
         menu.append(&sep)?;
     }
 
+    let logins = oauth::pending_logins(app);
+    for login in &logins {
+        let item = MenuItem::with_id(app, "disabled", login.label(), false, None::<&str>)?;
+        menu.append(&item)?;
+    }
+    if !logins.is_empty() {
+        menu.append(&sep)?;
+    }
+
+    let last_backup = MenuItem::with_id(
+        app,
+        "disabled",
+        schedule::last_backup_label(),
+        false,
+        None::<&str>,
+    )?;
+    let backup_now = MenuItem::with_id(
+        app,
+        "backup_now",
+        tr!("tray.backup_now"),
+        true,
+        None::<&str>,
+    )?;
+    let backups =
+        Submenu::with_items(app, tr!("tray.backups"), true, &[&last_backup, &backup_now])?;
+    menu.append(&backups)?;
+
+    let launch_at_login = autostart::is_enabled(app);
+    let autostart_i = CheckMenuItem::with_id(
+        app,
+        "autostart",
+        tr!("tray.launch_at_login"),
+        true,
+        launch_at_login,
+        None::<&str>,
+    )?;
+    let background_i = CheckMenuItem::with_id(
+        app,
+        "background_only",
+        tr!("tray.background_only"),
+        true,
+        settings.background_only,
+        None::<&str>,
+    )?;
+    menu.append(&autostart_i)?;
+    menu.append(&background_i)?;
+    menu.append(&sep)?;
     menu.append(&quit_i)?;
     Ok(menu)
 }
 
+/// Longest a rebuild may be held back while the menu looks open. There is no
+/// reliable "menu closed" event on every platform, so this is the backstop.
+const MAX_MENU_DEFER: Duration = Duration::from_secs(10);
+
+#[derive(Default)]
+struct MenuTrackerState {
+    open_since: Option<Instant>,
+    pending: bool,
+}
+
+/// Tracks whether the tray menu is (probably) open so rebuilds can wait.
+#[derive(Default)]
+struct MenuTracker(Mutex<MenuTrackerState>);
+
+fn menu_opened<R: Runtime>(app: &AppHandle<R>) {
+    if let Some(tracker) = app.try_state::<MenuTracker>() {
+        if let Ok(mut state) = tracker.0.lock() {
+            state.open_since = Some(Instant::now());
+        }
+    }
+}
+
+/// Marks the menu closed and applies any rebuild that was held back.
+fn menu_closed<R: Runtime>(app: &AppHandle<R>) {
+    let pending = app
+        .try_state::<MenuTracker>()
+        .and_then(|tracker| {
+            tracker.0.lock().ok().map(|mut state| {
+                state.open_since = None;
+                std::mem::take(&mut state.pending)
+            })
+        })
+        .unwrap_or(false);
+    if pending {
+        let _ = render_tray(app);
+    }
+}
+
+/// Returns true if the rebuild was queued instead of applied now.
+fn defer_while_menu_open<R: Runtime>(app: &AppHandle<R>) -> bool {
+    if !settings::load_settings().defer_tray_updates_while_open {
+        return false;
+    }
+    let Some(tracker) = app.try_state::<MenuTracker>() else {
+        return false;
+    };
+    let Ok(mut state) = tracker.0.lock() else {
+        return false;
+    };
+    let Some(opened) = state.open_since else {
+        return false;
+    };
+    if opened.elapsed() >= MAX_MENU_DEFER {
+        // Stale "open" marker: we never saw it close, so stop waiting.
+        state.open_since = None;
+        return false;
+    }
+
+    if !state.pending {
+        state.pending = true;
+        let remaining = MAX_MENU_DEFER.saturating_sub(opened.elapsed());
+        let app = app.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(remaining);
+            let app_for_closure = app.clone();
+            let _ = app.run_on_main_thread(move || menu_closed(&app_for_closure));
+        });
+    }
+    true
+}
+
+/// (5h, weekly) usage of whichever account the tray marks active, if known.
+fn active_usage<R: Runtime>(app: &AppHandle<R>) -> Option<(f64, f64)> {
+    let mut state = current_state(app);
+    mock::apply(app, &mut state);
+    let active = overrides::active_override(app).or(state.active_account.clone())?;
+    state
+        .usage_cache
+        .get(&active)
+        .filter(|entry| worst_percent(entry).is_some())
+        .map(usage_percentages)
+}
+
+/// Size of the template icon drawn for the macOS menubar.
+const TEMPLATE_ICON_SIZE: u32 = 44;
+
+/// Reflects the active account's usage on the tray itself: a ring showing 5h
+/// usage, colored by the worse limit (a template ring plus a percentage title
+/// on macOS). No data restores the default icon.
+fn set_tray_status<R: Runtime>(app: &AppHandle<R>, tray: &TrayIcon<R>) -> tauri::Result<()> {
+    let usage = active_usage(app);
+    let Some(default_icon) = app.default_window_icon() else {
+        return Ok(());
+    };
+
+    if cfg!(target_os = "macos") {
+        tray.set_title(usage.map(|(p5, _)| format!("{:.0}%", p5)))?;
+        match usage {
+            Some((p5, _)) => {
+                tray.set_icon(Some(tray_icon::template_ring(TEMPLATE_ICON_SIZE, p5)))?;
+                tray.set_icon_as_template(true)?;
+            }
+            None => {
+                tray.set_icon(Some(default_icon.clone()))?;
+                tray.set_icon_as_template(false)?;
+            }
+        }
+        return Ok(());
+    }
+
+    let icon = match usage {
+        Some((p5, pw)) => {
+            tray_icon::colored_ring(default_icon, p5, UsageLevel::of(p5.max(pw)).rgb())
+        }
+        None => default_icon.clone(),
+    };
+    tray.set_icon(Some(icon))
+}
+
+const TRAY_ID: &str = "main";
+
+/// Re-reads the vault and rebuilds the tray. For changes the app made itself.
 fn update_tray<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<()> {
-    if let Some(tray) = app.tray_by_id("main") {
+    reload_state(app);
+    render_tray(app)
+}
+
+/// Rebuilds the tray from the shared state as it is.
+fn render_tray<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<()> {
+    if defer_while_menu_open(app) {
+        return Ok(());
+    }
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
         let menu = build_tray_menu(app)?;
         tray.set_menu(Some(menu))?;
+        set_tray_status(app, &tray)?;
         // Also emit event to frontend
         let _ = app.emit("tray-config-changed", ());
     }
     Ok(())
 }
 
-fn start_watcher<R: Runtime>(app: AppHandle<R>) {
-    std::thread::spawn(move || {
-        let home = env::var("HOME").unwrap_or_default();
-        let root = PathBuf::from(home).join(".codex-accounts");
+/// Pause depth for the filesystem watcher. While non-zero, events are dropped;
+/// the last resume does one full reconcile so nothing is missed.
+#[derive(Default)]
+struct WatcherControl {
+    paused: AtomicUsize,
+}
 
-        // Watch root dir to catch multiple files (config.json AND usage_cache.json)
-        let watch_target = root;
+fn watcher_paused<R: Runtime>(app: &AppHandle<R>) -> bool {
+    app.try_state::<WatcherControl>()
+        .is_some_and(|c| c.paused.load(Ordering::SeqCst) > 0)
+}
 
-        // Channel to receive events
-        let (tx, rx) = channel();
+fn pause_watcher_internal<R: Runtime>(app: &AppHandle<R>) {
+    if let Some(control) = app.try_state::<WatcherControl>() {
+        control.paused.fetch_add(1, Ordering::SeqCst);
+    }
+}
 
-        // Create watcher
-        let mut watcher: Box<dyn Watcher> =
-            if let Ok(w) = RecommendedWatcher::new(tx, NotifyConfig::default()) {
-                Box::new(w)
-            } else {
-                eprintln!("Failed to create watcher");
-                return;
-            };
+fn resume_watcher_internal<R: Runtime>(app: &AppHandle<R>) {
+    let Some(control) = app.try_state::<WatcherControl>() else {
+        return;
+    };
+    let previous = control
+        .paused
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+            Some(n.saturating_sub(1))
+        })
+        .unwrap_or(0);
+    if previous <= 1 {
+        let _ = update_tray(app);
+    }
+}
+
+/// Runs a bulk operation with the watcher paused, reconciling once at the end.
+fn with_watcher_paused<R: Runtime, T>(app: &AppHandle<R>, f: impl FnOnce() -> T) -> T {
+    pause_watcher_internal(app);
+    let result = f();
+    resume_watcher_internal(app);
+    result
+}
+
+#[tauri::command]
+fn pause_watcher(app: AppHandle) {
+    pause_watcher_internal(&app);
+}
+
+#[tauri::command]
+fn resume_watcher(app: AppHandle) {
+    resume_watcher_internal(&app);
+}
+
+/// Quiet period the watcher waits for before acting on a burst of events.
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// How often the watcher retries a vault root it can't watch yet.
+const WATCH_ROOT_POLL: Duration = Duration::from_secs(3);
+
+/// What a burst of filesystem events touched, accumulated until it goes quiet.
+#[derive(Default)]
+struct PendingChanges {
+    /// `accounts/` or anything inside it: accounts added, removed or edited.
+    accounts: bool,
+    /// `usage_cache.json`.
+    usage: bool,
+    /// `config.json` (the active account, among other keys).
+    config: bool,
+    /// `settings.json`, including edits made outside the app.
+    settings: bool,
+}
+
+impl PendingChanges {
+    fn any(&self) -> bool {
+        self.accounts || self.usage || self.config || self.settings
+    }
 
-        // Watch directory recursively (simpler to just catch all)
-        if let Err(e) = watcher.watch(&watch_target, RecursiveMode::NonRecursive) {
-            eprintln!("Failed to watch config dir: {:?}", e);
+    /// Files elsewhere under the root (backups, logs, `.ipc/`) are ignored.
+    fn record(&mut self, root: &Path, path: &Path) {
+        let Ok(rel) = path.strip_prefix(root) else {
             return;
+        };
+        let mut components = rel.components();
+        match components.next().and_then(|c| c.as_os_str().to_str()) {
+            Some("accounts") => self.accounts = true,
+            Some("usage_cache.json") if components.next().is_none() => self.usage = true,
+            Some("config.json") if components.next().is_none() => self.config = true,
+            Some("settings.json") if components.next().is_none() => self.settings = true,
+            _ => {}
         }
+    }
+}
+
+/// A recursive watcher on the vault root, so account dirs and the files
+/// inside them are seen too.
+fn watch_root(
+    root: &Path,
+) -> Result<(RecommendedWatcher, Receiver<notify::Result<notify::Event>>), String> {
+    let (tx, rx) = channel();
+    let mut watcher = RecommendedWatcher::new(tx, NotifyConfig::default())
+        .map_err(|e| format!("Failed to create config watcher: {:?}", e))?;
+    watcher
+        .watch(root, RecursiveMode::Recursive)
+        .map_err(|e| format!("Failed to watch {}: {:?}", root.to_string_lossy(), e))?;
+    Ok((watcher, rx))
+}
 
+fn start_watcher<R: Runtime>(app: AppHandle<R>) {
+    std::thread::spawn(move || {
+        let root = codex_root();
+
+        // Setup normally creates the vault; if that failed (or it was removed
+        // since), keep trying instead of giving up on live updates for the
+        // whole session.
+        let mut waited = false;
+        let (_watcher, rx) = loop {
+            if recovery::ensure_vault().is_ok() {
+                match watch_root(&root) {
+                    Ok(watching) => break watching,
+                    Err(e) if !waited => log::warn!("{}; retrying", e),
+                    Err(_) => {}
+                }
+            }
+            waited = true;
+            std::thread::sleep(WATCH_ROOT_POLL);
+        };
+        if waited {
+            log::info!("Watching {}", root.to_string_lossy());
+            let app_for_closure = app.clone();
+            let _ = app.run_on_main_thread(move || {
+                let _ = update_tray(&app_for_closure);
+            });
+        }
+        // Events report resolved paths (e.g. /private/var on macOS).
+        let root = fs::canonicalize(&root).unwrap_or(root);
+
+        // Events are coalesced: a burst (CLI rewriting several files, editor
+        // atomic saves) is handled once WATCH_DEBOUNCE after it goes quiet.
+        let mut pending = PendingChanges::default();
+        let mut last_active = current_state(&app).active_account;
         loop {
-            match rx.recv() {
+            let received = if pending.any() {
+                rx.recv_timeout(WATCH_DEBOUNCE)
+            } else {
+                rx.recv().map_err(|_| RecvTimeoutError::Disconnected)
+            };
+
+            match received {
                 Ok(Ok(event)) => {
-                    // Check if it's a write or modify
-                    if event.kind.is_modify() || event.kind.is_create() {
-                        // Check path
-                        let should_update = event.paths.iter().any(|p| {
-                            if let Some(name) = p.file_name() {
-                                name == "config.json"
-                                    || name == "usage_cache.json"
-                                    || name == "accounts"
-                            } else {
-                                false
-                            }
+                    if event.kind.is_modify() || event.kind.is_create() || event.kind.is_remove() {
+                        for path in &event.paths {
+                            pending.record(&root, path);
+                        }
+                    }
+                }
+                Ok(Err(e)) => log::warn!("Watch error: {:?}", e),
+                Err(RecvTimeoutError::Timeout) => {
+                    let changes = std::mem::take(&mut pending);
+                    let state = refresh_state(&app, &changes);
+                    if changes.usage {
+                        if let Err(e) = history::record_snapshot(&state) {
+                            log::warn!("Failed to record usage history: {}", e);
+                        }
+                        alerts::check(&app, &state);
+                        autoswitch::evaluate(&app, &state);
+                    }
+                    // Switches made by the app itself pause the watcher and
+                    // refresh on their own; they still move `last_active`.
+                    let active_changed = changes.config && state.active_account != last_active;
+                    last_active = state.active_account.clone();
+                    if changes.settings {
+                        let app_for_closure = app.clone();
+                        let _ = app.run_on_main_thread(move || {
+                            settings::apply(&app_for_closure, settings::load_settings());
                         });
+                    }
+                    if watcher_paused(&app) {
+                        continue;
+                    }
 
-                        if should_update {
-                            let app_clone = app.clone();
-                            let app_for_closure = app_clone.clone();
-                            // Debounce slightly or just run?
-                            // Run on main thread to update tray
-                            let _ = app_clone.run_on_main_thread(move || {
-                                let _ = update_tray(&app_for_closure);
-                            });
-                        }
+                    report_load_warnings(&app, &state);
+                    if changes.accounts {
+                        let _ = app.emit("accounts-changed", &state.accounts);
                     }
+                    if changes.usage {
+                        let _ = app.emit("usage-changed", ());
+                    }
+                    if active_changed {
+                        log::info!(
+                            "Active account changed outside the app: {:?}",
+                            state.active_account
+                        );
+                        let _ = app.emit("active-changed", &state.active_account);
+                    }
+
+                    let app_for_closure = app.clone();
+                    // Run on main thread to update tray
+                    let _ = app.run_on_main_thread(move || {
+                        if let Err(e) = render_tray(&app_for_closure) {
+                            log::warn!("Failed to update tray: {}", e);
+                        }
+                    });
                 }
-                Ok(Err(e)) => eprintln!("Watch error: {:?}", e),
-                Err(_) => break, // Channel closed
+                Err(RecvTimeoutError::Disconnected) => break, // Channel closed
             }
         }
     });
 }
 
+/// A user-initiated switch (tray item, hotkey). While a Codex session is
+/// running the switch is queued until it ends, with a notification; failures
+/// are reported to the webview instead of returned. Returns whether the
+/// switch happened right away.
+fn switch_from_ui(app: &AppHandle, name: &str) -> bool {
+    match accounts::switch_account(app.clone(), name.to_string(), None, Some(true)) {
+        Ok(session::SwitchOutcome::Switched) => true,
+        Ok(session::SwitchOutcome::Queued) => {
+            notify(
+                app,
+                &tr!("notify.switch_queued.title"),
+                &tr!("notify.switch_queued.body", name = name),
+            );
+            false
+        }
+        Err(e) => {
+            log::error!("Failed to switch to '{}': {}", name, e);
+            let _ = app.emit("switch-failed", e);
+            false
+        }
+    }
+}
+
+/// Switches to the account after the active one in tray order, wrapping
+/// around, and announces the new account with its usage. With
+/// `cycle_skips_exhausted`, accounts at either limit are passed over.
+fn cycle_account(app: &AppHandle) {
+    let state = current_state(app);
+    if state.accounts.len() < 2 {
+        return;
+    }
+    let skip_exhausted = settings::load_settings().cycle_skips_exhausted;
+    let current = overrides::active_override(app).or(state.active_account.clone());
+    let Some(next) = rotation::next_in_rotation(&state.accounts, current.as_deref(), |n| {
+        !skip_exhausted || rotation::is_usable(&state, n)
+    }) else {
+        return;
+    };
+    if switch_from_ui(app, &next) {
+        let body = match account_usage(&state, &next) {
+            Some((p5, pw)) => tr!(
+                "tray.usage",
+                name = next,
+                p5 = format!("{:.0}", p5),
+                pw = format!("{:.0}", pw),
+            ),
+            None => next.clone(),
+        };
+        notify(app, &tr!("notify.switched.title"), &body);
+    }
+}
+
+/// Registers `hotkey` before releasing `previous`, so a bad binding leaves
+/// the old one in place. A `hotkey` that is already registered is kept.
+#[cfg(desktop)]
+fn rebind_hotkey<R: Runtime>(
+    app: &AppHandle<R>,
+    previous: &str,
+    hotkey: &str,
+) -> Result<(), String> {
+    use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+    let shortcut: Shortcut = hotkey
+        .parse()
+        .map_err(|e| format!("Invalid hotkey '{}': {}", hotkey, e))?;
+    let previous = previous.parse::<Shortcut>().ok();
+    if previous == Some(shortcut) {
+        return Ok(());
+    }
+    let shortcuts = app.global_shortcut();
+    if !shortcuts.is_registered(shortcut) {
+        shortcuts
+            .register(shortcut)
+            .map_err(|e| format!("Failed to register hotkey '{}': {}", hotkey, e))?;
+    }
+    if let Some(previous) = previous {
+        let _ = shortcuts.unregister(previous);
+    }
+    Ok(())
+}
+
+#[cfg(not(desktop))]
+fn rebind_hotkey<R: Runtime>(
+    _app: &AppHandle<R>,
+    _previous: &str,
+    _hotkey: &str,
+) -> Result<(), String> {
+    Err("Global hotkeys are only available on desktop".to_string())
+}
+
+/// Rebinds the cycle hotkey and persists it, optionally along with whether
+/// cycling skips exhausted accounts.
+#[tauri::command]
+fn set_hotkey(app: AppHandle, hotkey: String, skip_exhausted: Option<bool>) -> Result<(), String> {
+    let mut settings = settings::load_settings();
+    rebind_hotkey(&app, &settings.cycle_hotkey, &hotkey)?;
+    settings.cycle_hotkey = hotkey;
+    if let Some(skip) = skip_exhausted {
+        settings.cycle_skips_exhausted = skip;
+    }
+    settings::save_settings(&settings)
+}
+
+/// Shows, unminimizes and focuses the main window. Returns whether it exists.
+fn show_main_window<R: Runtime>(app: &AppHandle<R>) -> bool {
+    let Some(window) = app.get_webview_window("main") else {
+        return false;
+    };
+    let _ = window.show();
+    let _ = window.unminimize();
+    let _ = window.set_focus();
+    true
+}
+
 pub fn run() {
-    tauri::Builder::default()
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(code) = cli::run_local(&args) {
+        std::process::exit(code);
+    }
+
+    let builder = tauri::Builder::default();
+    // Must be the first plugin. Deep-link URLs in a second launch's args are
+    // routed to `on_open_url` by the plugin, so only flags are handled here.
+    #[cfg(desktop)]
+    let builder = builder.plugin(tauri_plugin_single_instance::init(|app, argv, _cwd| {
+        // A bare relaunch means "show me the app"; scripted ones stay in the tray.
+        if cli::parse_args(&argv, true).is_empty() {
+            show_main_window(app);
+        }
+        cli::handle_args(app, &argv, false);
+    }));
+    #[cfg(desktop)]
+    let builder = builder.plugin(tauri_plugin_autostart::init(
+        tauri_plugin_autostart::MacosLauncher::LaunchAgent,
+        None,
+    ));
+    #[cfg(debug_assertions)]
+    let builder = builder.manage(mock::MockUsage::default());
+    builder
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_shell::init())
-        .setup(|app| {
-            // Log setup
-            if cfg!(debug_assertions) {
-                app.handle().plugin(
-                    tauri_plugin_log::Builder::default()
-                        .level(log::LevelFilter::Info)
-                        .build(),
-                )?;
+        .plugin(tauri_plugin_notification::init())
+        // Repairs the vault before the first read below.
+        .manage(recovery::prepare())
+        .manage(SharedState(Mutex::new(load_state())))
+        .manage(overrides::OverrideStack::default())
+        .manage(alerts::UsageAlerts::default())
+        .manage(autoswitch::AutoSwitch::default())
+        .manage(MenuTracker::default())
+        .manage(WatcherControl::default())
+        .manage(rotation::Rotation::default())
+        .manage(health::HealthStatuses::default())
+        .manage(session::PendingSwitch::default())
+        .manage(oauth::LoginFlows::default())
+        .manage(sync::SyncControl::default())
+        .manage(settings::AppliedSettings::default())
+        .invoke_handler(tauri::generate_handler![
+            pause_watcher,
+            resume_watcher,
+            set_hotkey,
+            get_accounts_state,
+            accounts::import_accounts_csv,
+            accounts::switch_account,
+            accounts::list_accounts,
+            accounts::add_account,
+            accounts::remove_account,
+            accounts::rename_account,
+            accounts::get_usage,
+            archive::inspect_archive,
+            autoswitch::auto_switch_log,
+            autostart::get_autostart,
+            autostart::set_autostart,
+            autostart::set_background_only,
+            archive::import_accounts,
+            backup::diff_backups,
+            backup::backup_account,
+            backup::list_backups,
+            backup::restore_account,
+            backup::create_backup,
+            backup::restore_backup,
+            backup::verify_backup,
+            backup::preview_restore,
+            polling::refresh_usage,
+            secrets::migrate_secrets,
+            session::cancel_pending_switch,
+            session::pending_switch,
+            secrets::export_plaintext,
+            #[cfg(debug_assertions)]
+            fixture::export_fixture,
+            health::cache_coverage,
+            health::check_account_health,
+            health::case_collisions,
+            health::check_broken_accounts,
+            health::check_expiring_tokens,
+            health::check_missing_backups,
+            health::check_over_limit,
+            health::check_permissions,
+            health::health_check,
+            health::vault_score,
+            history::get_usage_history,
+            history::get_usage_summary,
+            history::usage_trends,
+            metadata::get_account_metadata,
+            metadata::set_account_metadata,
+            #[cfg(debug_assertions)]
+            mock::set_mock_reset,
+            oauth::cancel_account_login,
+            oauth::start_account_login,
+            overrides::push_active_override,
+            process::codex_running,
+            projects::list_project_accounts,
+            projects::remove_project_account,
+            projects::report_cwd,
+            projects::set_project_account,
+            rotation::start_rotation,
+            rotation::stop_rotation,
+            rotation::rotation_status,
+            schedule::backup_vault_now,
+            schedule::get_backup_schedule,
+            schedule::set_backup_schedule,
+            overrides::pop_active_override,
+            settings::effective_config,
+            settings::get_settings,
+            settings::update_settings,
+            settings::export_settings,
+            settings::import_settings,
+            settings::label_group,
+            settings::set_account_group,
+            settings::set_account_label,
+            settings::set_pause_automations,
+            settings::set_notifications,
+            transfer::export_vault,
+            transfer::import_vault,
+            settings::set_pin_active_to_top,
+            settings::set_login_watch,
+            settings::set_defer_tray_updates,
+            settings::set_usage_trends,
+            settings::set_warn_if_codex_running,
+            sync::configure_sync,
+            sync::sync_now,
+            sync::sync_status,
+            logs::get_recent_logs,
+            logs::open_log_folder,
+            logs::export_support_bundle,
+            recovery::get_vault_status,
+            logins::import_live_login,
+            dashboard::get_dashboard,
+            lifecycle::clone_account,
+            lifecycle::archive_account,
+            lifecycle::unarchive_account,
+            lifecycle::list_archived_accounts,
+            lifecycle::delete_account,
+            lifecycle::list_deleted_accounts,
+            lifecycle::restore_deleted_account,
+            i18n::get_locale,
+            usage::cache_memory_estimate,
+            usage::get_raw_usage,
+        ])
+        .on_window_event(|window, event| {
+            // In background-only mode the app lives in the tray; closing the
+            // manager just hides it.
+            if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                if settings::load_settings().background_only {
+                    api.prevent_close();
+                    let _ = window.hide();
+                }
             }
+        })
+        .setup(|app| {
+            app.handle().plugin(logs::plugin())?;
+            i18n::set_locale(&settings::load_settings().locale);
+            recovery::report(app.handle());
+
+            autostart::apply_activation_policy(
+                app.handle(),
+                settings::load_settings().background_only,
+            );
 
             let menu = build_tray_menu(app.handle())?;
 
-            let _tray = TrayIconBuilder::with_id("main")
-                .icon(app.default_window_icon().unwrap().clone())
+            // Tray ids are per process, but a setup that runs again (or a
+            // half-torn-down tray) must not leave two icons or fail outright.
+            if app.remove_tray_by_id(TRAY_ID).is_some() {
+                log::warn!("Replaced an existing tray icon");
+            }
+            let mut tray = TrayIconBuilder::with_id(TRAY_ID);
+            if let Some(icon) = app.default_window_icon() {
+                tray = tray.icon(icon.clone());
+            }
+            let tray = tray
                 .menu(&menu)
                 .on_menu_event(move |app, event| {
+                    // Choosing an item closes the menu.
+                    menu_closed(app);
                     let id = event.id.as_ref();
                     if id == "quit" {
                         app.exit(0);
                     } else if id == "open" || id == "add" {
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.show();
-                            let _ = window.set_focus();
-                            if id == "add" {
-                                let _ = app.emit("tray-add-account", ());
-                            }
+                        if show_main_window(app) && id == "add" {
+                            let _ = app.emit("tray-add-account", ());
                         }
-                    } else if id.starts_with("switch:") {
-                        let account_name = id.trim_start_matches("switch:");
-                        // Strip usage info if present (unlikely if loop passes clean name to id)
-                        // Wait, build_tray_menu makes id="switch:{name}" (clean name).
-                        let _ = app.emit("tray-switch-account", account_name);
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.show();
-                            let _ = window.set_focus();
+                    } else if id == "autostart" {
+                        if let Err(e) = autostart::toggle(app) {
+                            log::warn!("{}", e);
+                            notify(app, &tr!("notify.launch_at_login.title"), &e);
                         }
+                    } else if id == "background_only" {
+                        let enabled = !settings::load_settings().background_only;
+                        if let Err(e) = autostart::set_background_only(app.clone(), enabled) {
+                            log::warn!("Failed to save background-only mode: {}", e);
+                        }
+                    } else if id == "backup_now" {
+                        if let Err(e) = schedule::backup_vault_now(app.clone()) {
+                            log::error!("Vault backup failed: {}", e);
+                            notify(app, &tr!("notify.backup_failed.title"), &e);
+                        }
+                    } else if let Some(account_name) = id.strip_prefix("switch:") {
+                        let _ = switch_from_ui(app, account_name);
                     }
                 })
-                .on_tray_icon_event(|_tray, event| {
-                    if let TrayIconEvent::Click {
-                        button: MouseButton::Left,
+                .on_tray_icon_event(|tray, event| match event {
+                    TrayIconEvent::Click {
+                        button_state: MouseButtonState::Down,
                         ..
-                    } = event
-                    {
-                        // Handle click
-                    }
+                    } => menu_opened(tray.app_handle()),
+                    TrayIconEvent::Leave { .. } => menu_closed(tray.app_handle()),
+                    _ => {}
                 })
                 .build(app)?;
+            set_tray_status(app.handle(), &tray)?;
+            report_load_warnings(app.handle(), &current_state(app.handle()));
+
+            #[cfg(desktop)]
+            {
+                use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
+                app.handle().plugin(
+                    tauri_plugin_global_shortcut::Builder::new()
+                        .with_handler(|app, _shortcut, event| {
+                            if event.state() == ShortcutState::Pressed {
+                                cycle_account(app);
+                            }
+                        })
+                        .build(),
+                )?;
+                let hotkey = settings::load_settings().cycle_hotkey;
+                if let Err(e) = app.global_shortcut().register(hotkey.as_str()) {
+                    log::warn!("Failed to register hotkey '{}': {}", hotkey, e);
+                }
+            }
+
+            #[cfg(desktop)]
+            {
+                use tauri_plugin_deep_link::DeepLinkExt;
+                // Installed builds register the scheme at bundle time; this
+                // covers dev builds and AppImages.
+                #[cfg(any(windows, target_os = "linux"))]
+                if let Err(e) = app.deep_link().register_all() {
+                    log::warn!("Failed to register deep-link scheme: {}", e);
+                }
+                let handle = app.handle().clone();
+                app.deep_link().on_open_url(move |event| {
+                    for url in event.urls() {
+                        match cli::parse_url(url.as_str()) {
+                            Some(action) => cli::execute(&handle, action),
+                            None => log::warn!("Ignoring unknown deep link {}", url),
+                        }
+                    }
+                });
+            }
+
+            secrets::migrate_on_launch(app.handle());
+            lifecycle::purge_expired_trash();
 
             // Start Watcher
+            alerts::seed(app.handle());
             start_watcher(app.handle().clone());
+            logins::start_login_watcher(app.handle().clone());
+            rotation::resume_if_enabled(app.handle());
+            ipc::start_ipc_watcher(app.handle().clone());
+            polling::start_usage_poller(app.handle().clone());
+            health::start_health_checker(app.handle().clone());
+            schedule::start_backup_scheduler(app.handle().clone());
+            resets::start_reset_watcher(app.handle().clone());
+            sync::restart(app.handle());
+
+            // Flags (and, on Windows/Linux, the deep link) this instance was launched with.
+            cli::handle_args(app.handle(), &args, true);
 
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let RunEvent::Exit = event {
+                // Temporary overrides never outlive the app.
+                if overrides::active_override(app).is_some() {
+                    if let Err(e) = overrides::restore_persisted() {
+                        log::warn!("Failed to restore active account on exit: {}", e);
+                    }
+                }
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accounts_come_from_the_env_override() {
+        let dir = std::env::temp_dir().join(format!("lib-accounts-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let home = Path::new("/nonexistent-home");
+
+        // A CODEX_ACCOUNTS_DIR that doesn't exist yet is an empty vault.
+        let (root, origin) =
+            paths::resolve_root(Some(dir.to_string_lossy().into_owned()), home, None);
+        assert_eq!(
+            (root.as_path(), origin),
+            (dir.as_path(), paths::RootOrigin::Env)
+        );
+        assert!(accounts_under(&root).is_empty());
+
+        for name in ["work", "home", ".hidden"] {
+            fs::create_dir_all(root.join("accounts").join(name)).unwrap();
+        }
+        fs::write(root.join("accounts").join("notes.txt"), "").unwrap();
+        assert_eq!(accounts_under(&root), ["home", "work"]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    fn entry(p5: f64, pw: f64) -> CacheEntry {
+        CacheEntry::from_raw(serde_json::json!({
+            "limits": {
+                "limit_5h": { "used": p5, "limit": 100 },
+                "limit_weekly": { "used": pw, "limit": 100 },
+            }
+        }))
+    }
+
+    #[test]
+    fn headroom_order_puts_unknown_usage_last() {
+        let cache = HashMap::from([
+            ("busy".to_string(), entry(90.0, 10.0)),
+            ("idle".to_string(), entry(5.0, 20.0)),
+            ("weekly".to_string(), entry(10.0, 60.0)),
+        ]);
+        let accounts = ["zed", "weekly", "busy", "alpha", "idle"]
+            .map(str::to_string)
+            .to_vec();
+        assert_eq!(
+            sort_by_headroom(accounts, &cache),
+            ["idle", "weekly", "busy", "alpha", "zed"]
+        );
+    }
 }
@@ -0,0 +1,329 @@
+//! Cloning, archiving and soft-deleting accounts.
+//!
+//! Archived accounts move to `archived/<name>/`: out of `accounts/`, so the
+//! tray and the CLI no longer list them, but still part of vault backups.
+//! Deleted accounts move to `trash/<id>/` next to a `deleted.json` record and
+//! can be restored until they are `TRASH_RETENTION_DAYS` old, after which
+//! they are purged for good. Keychain-held credentials move to their own
+//! keys (`archived/<name>`, `trash/<id>`) with the account.
+//!
+//! Each command emits an event (`account-cloned`, `account-archived`,
+//! `account-unarchived`, `account-deleted`, `account-restored`) and rebuilds
+//! the tray.
+
+use chrono::{DateTime, Duration, Local};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter};
+
+use crate::accounts::{
+    account_dir, clear_active_account, validate_account_name, write_active_account, write_private,
+};
+use crate::backup::{list_files, new_timestamp};
+use crate::i18n::tr;
+use crate::secrets::{self, KEYCHAIN_MARKER};
+use crate::{codex_root, load_state, update_tray};
+
+/// How long a deleted account can be restored.
+const TRASH_RETENTION_DAYS: i64 = 7;
+
+const DELETED_RECORD: &str = "deleted.json";
+
+pub fn archived_dir() -> PathBuf {
+    codex_root().join("archived")
+}
+
+fn trash_dir() -> PathBuf {
+    codex_root().join("trash")
+}
+
+fn archived_key(name: &str) -> String {
+    format!("archived/{}", name)
+}
+
+fn trash_key(id: &str) -> String {
+    format!("trash/{}", id)
+}
+
+fn has_keychain_secret(dir: &Path) -> bool {
+    dir.join(KEYCHAIN_MARKER).exists()
+}
+
+/// Moves an account directory, undoing the move if its keychain entry
+/// can't follow.
+fn move_account(from: &Path, to: &Path, from_key: &str, to_key: &str) -> Result<(), String> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.to_string_lossy(), e))?;
+    }
+    fs::rename(from, to).map_err(|e| {
+        format!(
+            "Failed to move {} to {}: {}",
+            from.to_string_lossy(),
+            to.to_string_lossy(),
+            e
+        )
+    })?;
+    if has_keychain_secret(to) {
+        if let Err(e) = secrets::move_secret(from_key, to_key) {
+            let _ = fs::rename(to, from);
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+/// Clears `active_account` if it was `name`.
+fn deactivate(name: &str) -> Result<(), String> {
+    if load_state().active_account.as_deref() == Some(name) {
+        clear_active_account()?;
+    }
+    Ok(())
+}
+
+#[derive(serde::Serialize, Clone)]
+struct ClonedPayload {
+    source: String,
+    name: String,
+}
+
+/// Copies account `src` to a new account `dest`: credentials (including a
+/// keychain entry) and metadata alike.
+#[tauri::command]
+pub fn clone_account(app: AppHandle, src: String, dest: String) -> Result<(), String> {
+    validate_account_name(&src)?;
+    validate_account_name(&dest)?;
+    let source = account_dir(&src);
+    if !source.is_dir() {
+        return Err(tr!("error.account_not_found", name = src));
+    }
+    let target = account_dir(&dest);
+    if target.exists() {
+        return Err(tr!("error.account_exists", name = dest));
+    }
+
+    let copy = || -> Result<(), String> {
+        for (rel, path) in list_files(&source)? {
+            let bytes = fs::read(&path)
+                .map_err(|e| format!("Failed to read {}: {}", path.to_string_lossy(), e))?;
+            write_private(&target.join(&rel), &bytes)?;
+        }
+        if has_keychain_secret(&source) {
+            secrets::copy_secret(&src, &dest)?;
+        }
+        Ok(())
+    };
+    // Don't leave a half-copied account behind.
+    copy().inspect_err(|_| {
+        let _ = fs::remove_dir_all(&target);
+    })?;
+
+    log::info!("Cloned '{}' to '{}'", src, dest);
+    let _ = app.emit(
+        "account-cloned",
+        ClonedPayload {
+            source: src,
+            name: dest,
+        },
+    );
+    let _ = update_tray(&app);
+    Ok(())
+}
+
+/// Hides an account from the tray without deleting it. Archiving the active
+/// account leaves no account active.
+#[tauri::command]
+pub fn archive_account(app: AppHandle, name: String) -> Result<(), String> {
+    validate_account_name(&name)?;
+    let source = account_dir(&name);
+    if !source.is_dir() {
+        return Err(tr!("error.account_not_found", name = name));
+    }
+    let dest = archived_dir().join(&name);
+    if dest.exists() {
+        return Err(format!("An archived account '{}' already exists", name));
+    }
+    move_account(&source, &dest, &name, &archived_key(&name))?;
+    deactivate(&name)?;
+
+    log::info!("Archived '{}'", name);
+    let _ = app.emit("account-archived", &name);
+    let _ = update_tray(&app);
+    Ok(())
+}
+
+/// Brings an archived account back into the vault.
+#[tauri::command]
+pub fn unarchive_account(app: AppHandle, name: String) -> Result<(), String> {
+    validate_account_name(&name)?;
+    let source = archived_dir().join(&name);
+    if !source.is_dir() {
+        return Err(format!("No archived account '{}'", name));
+    }
+    let dest = account_dir(&name);
+    if dest.exists() {
+        return Err(tr!("error.account_exists", name = name));
+    }
+    move_account(&source, &dest, &archived_key(&name), &name)?;
+
+    log::info!("Unarchived '{}'", name);
+    let _ = app.emit("account-unarchived", &name);
+    let _ = update_tray(&app);
+    Ok(())
+}
+
+/// Archived account names, alphabetically.
+#[tauri::command]
+pub fn list_archived_accounts() -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(archived_dir())
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| e.path().is_dir())
+                .filter_map(|e| e.file_name().to_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+/// A soft-deleted account, as recorded in its trash folder.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct DeletedAccount {
+    /// Trash folder name; what `restore_deleted_account` takes.
+    id: String,
+    name: String,
+    /// RFC 3339.
+    deleted_at: String,
+    /// RFC 3339; the account is purged after this.
+    restorable_until: String,
+    was_active: bool,
+}
+
+impl DeletedAccount {
+    fn expired(&self, now: DateTime<Local>) -> bool {
+        DateTime::parse_from_rfc3339(&self.restorable_until).map_or(true, |until| now > until)
+    }
+}
+
+fn read_deleted(id: &str) -> Option<DeletedAccount> {
+    if id.is_empty() || id.starts_with('.') || id.contains(['/', '\\']) {
+        return None;
+    }
+    fs::read_to_string(trash_dir().join(id).join(DELETED_RECORD))
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+}
+
+/// Moves an account to the trash. It can be brought back with
+/// `restore_deleted_account` for `TRASH_RETENTION_DAYS`.
+#[tauri::command]
+pub fn delete_account(app: AppHandle, name: String) -> Result<DeletedAccount, String> {
+    validate_account_name(&name)?;
+    let source = account_dir(&name);
+    if !source.is_dir() {
+        return Err(tr!("error.account_not_found", name = name));
+    }
+
+    purge_expired_trash();
+
+    let id = format!("{}-{}", new_timestamp(), name);
+    let now = Local::now();
+    let record = DeletedAccount {
+        id: id.clone(),
+        name: name.clone(),
+        deleted_at: now.to_rfc3339(),
+        restorable_until: (now + Duration::days(TRASH_RETENTION_DAYS)).to_rfc3339(),
+        was_active: load_state().active_account.as_deref() == Some(name.as_str()),
+    };
+    let body = serde_json::to_vec_pretty(&record)
+        .map_err(|e| format!("Failed to serialize deletion record: {}", e))?;
+    let folder = trash_dir().join(&id);
+    let trashed = folder.join("account");
+    move_account(&source, &trashed, &name, &trash_key(&id))?;
+    // Without its record the account couldn't be restored; put it back.
+    if let Err(e) = write_private(&folder.join(DELETED_RECORD), &body) {
+        let _ = move_account(&trashed, &source, &trash_key(&id), &name);
+        let _ = fs::remove_dir_all(&folder);
+        return Err(e);
+    }
+    if record.was_active {
+        clear_active_account()?;
+    }
+
+    log::info!("Moved '{}' to the trash as {}", name, id);
+    let _ = app.emit("account-deleted", &record);
+    let _ = update_tray(&app);
+    Ok(record)
+}
+
+/// Accounts in the trash that can still be restored, newest first.
+#[tauri::command]
+pub fn list_deleted_accounts() -> Vec<DeletedAccount> {
+    let now = Local::now();
+    let mut deleted: Vec<DeletedAccount> = fs::read_dir(trash_dir())
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|e| read_deleted(e.file_name().to_str()?))
+                .filter(|d| !d.expired(now))
+                .collect()
+        })
+        .unwrap_or_default();
+    deleted.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+    deleted
+}
+
+/// Puts a deleted account back under its old name, active again if it was
+/// when deleted and nothing else has been made active since.
+#[tauri::command]
+pub fn restore_deleted_account(app: AppHandle, id: String) -> Result<String, String> {
+    let record = read_deleted(&id)
+        .filter(|d| !d.expired(Local::now()))
+        .ok_or_else(|| format!("Deleted account '{}' not found or no longer restorable", id))?;
+    let dest = account_dir(&record.name);
+    if dest.exists() {
+        return Err(tr!("error.account_exists", name = record.name));
+    }
+    let folder = trash_dir().join(&id);
+    move_account(
+        &folder.join("account"),
+        &dest,
+        &trash_key(&id),
+        &record.name,
+    )?;
+    let _ = fs::remove_dir_all(&folder);
+    if record.was_active && load_state().active_account.is_none() {
+        write_active_account(&record.name)?;
+    }
+
+    log::info!("Restored '{}' from the trash", record.name);
+    let _ = app.emit("account-restored", &record.name);
+    let _ = update_tray(&app);
+    Ok(record.name)
+}
+
+/// Permanently removes trashed accounts past their undo window, along with
+/// their keychain entries. Unreadable trash folders are left alone.
+pub fn purge_expired_trash() {
+    let Ok(entries) = fs::read_dir(trash_dir()) else {
+        return;
+    };
+    let now = Local::now();
+    for entry in entries.flatten() {
+        let Some(id) = entry.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        let Some(record) = read_deleted(&id).filter(|d| d.expired(now)) else {
+            continue;
+        };
+        if has_keychain_secret(&entry.path().join("account")) {
+            secrets::forget(&trash_key(&id));
+        }
+        match fs::remove_dir_all(entry.path()) {
+            Ok(()) => log::info!("Purged deleted account '{}' ({})", record.name, id),
+            Err(e) => log::warn!("Failed to purge {}: {}", id, e),
+        }
+    }
+}
@@ -0,0 +1,290 @@
+use notify::{Config as NotifyConfig, RecommendedWatcher, RecursiveMode, Watcher};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::sync::mpsc::channel;
+use tauri::{AppHandle, Emitter, Runtime};
+
+use crate::accounts::{
+    account_dir, clear_active_account, validate_account_name, write_active_account, write_private,
+};
+use crate::health::{jwt_claims, read_credentials};
+use crate::i18n::tr;
+use crate::paths::live_auth_path;
+use crate::settings::load_settings;
+use crate::{load_state, notify, overrides, update_tray};
+
+/// Stable identity for a set of credentials, independent of token refreshes.
+///
+/// Works on both vault records (`tokens` nested) and the flattened live file.
+pub fn credential_fingerprint(creds: &serde_json::Value) -> Option<String> {
+    let tokens = creds.get("tokens").unwrap_or(creds);
+
+    let identity = if let Some(key) = creds["api_key"]
+        .as_str()
+        .or_else(|| creds["OPENAI_API_KEY"].as_str())
+    {
+        format!("api_key:{}", key)
+    } else if let Some(id) = tokens["account_id"].as_str() {
+        format!("account:{}", id)
+    } else if let Some(sub) = tokens["id_token"]
+        .as_str()
+        .and_then(jwt_claims)
+        .and_then(|c| c["sub"].as_str().map(str::to_string))
+    {
+        format!("sub:{}", sub)
+    } else if let Some(refresh) = tokens["refresh_token"].as_str() {
+        format!("refresh:{}", refresh)
+    } else {
+        format!("access:{}", tokens["access_token"].as_str()?)
+    };
+
+    Some(format!("{:x}", Sha256::digest(identity.as_bytes())))
+}
+
+/// Which vault account a set of live credentials belongs to.
+enum LiveOwner {
+    Account(String),
+    /// Not any vault account.
+    Unknown,
+    /// No match, but some accounts are encrypted and can't be compared.
+    Unsure,
+}
+
+fn live_owner(fingerprint: &str) -> LiveOwner {
+    let mut unsure = false;
+    for name in load_state().accounts {
+        match read_credentials(&name) {
+            Ok(Some(creds)) => {
+                if credential_fingerprint(&creds).as_deref() == Some(fingerprint) {
+                    return LiveOwner::Account(name);
+                }
+            }
+            _ => unsure = true,
+        }
+    }
+    if unsure {
+        LiveOwner::Unsure
+    } else {
+        LiveOwner::Unknown
+    }
+}
+
+#[derive(serde::Serialize, Clone)]
+struct NewLoginPayload {
+    fingerprint: String,
+    email: Option<String>,
+    imported_as: Option<String>,
+}
+
+/// Turns the live (flattened) auth file back into a vault account record.
+fn account_record(name: &str, live: &serde_json::Value) -> serde_json::Value {
+    let mut record = serde_json::json!({
+        "name": name,
+        "created_at": chrono::Local::now().to_rfc3339(),
+    });
+    if let Some(email) = live["email"].as_str() {
+        record["email"] = email.into();
+    }
+    if let Some(key) = live["api_key"]
+        .as_str()
+        .or_else(|| live["OPENAI_API_KEY"].as_str())
+    {
+        record["api_key"] = key.into();
+        record["type"] = "api_key".into();
+    } else if let Some(tokens) = live.get("tokens") {
+        record["tokens"] = tokens.clone();
+        record["type"] = "oauth".into();
+    }
+    record
+}
+
+/// Saves the live login as `name`, by default `login-<timestamp>`; the user
+/// can rename it later.
+fn import_login(live: &serde_json::Value, name: Option<String>) -> Result<String, String> {
+    let name =
+        name.unwrap_or_else(|| format!("login-{}", chrono::Local::now().format("%Y%m%d-%H%M%S")));
+    validate_account_name(&name)?;
+    let dir = account_dir(&name);
+    if dir.exists() {
+        return Err(tr!("error.account_exists", name = name));
+    }
+    let body = serde_json::to_vec_pretty(&account_record(&name, live))
+        .map_err(|e| format!("Failed to serialize login: {}", e))?;
+    write_private(&dir.join("auth.json"), &body)?;
+    Ok(name)
+}
+
+fn read_live_auth() -> Option<serde_json::Value> {
+    fs::read_to_string(live_auth_path())
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+}
+
+/// Brings `active_account` in line with the live credentials after they
+/// changed outside the app (e.g. `codex login`). The config write is picked up
+/// by the vault watcher, which refreshes the tray and emits `active-changed`.
+fn reconcile_live_auth<R: Runtime>(app: &AppHandle<R>, last_seen: &mut Option<String>) {
+    let Some(live) = read_live_auth() else {
+        return;
+    };
+    let Some(fingerprint) = credential_fingerprint(&live) else {
+        return;
+    };
+
+    // Token refreshes rewrite the file without changing identity.
+    if last_seen.as_deref() == Some(fingerprint.as_str()) {
+        return;
+    }
+    *last_seen = Some(fingerprint.clone());
+
+    // Overrides swap the live credentials without touching the config.
+    if overrides::active_override(app).is_some() {
+        return;
+    }
+    let active = load_state().active_account;
+    match live_owner(&fingerprint) {
+        LiveOwner::Account(name) => {
+            if active.as_deref() != Some(name.as_str()) {
+                log::info!(
+                    "Codex is now logged in as '{}' (was {:?}); updating the active account",
+                    name,
+                    active
+                );
+                if let Err(e) = write_active_account(&name) {
+                    log::warn!("Failed to mark '{}' active: {}", name, e);
+                }
+            }
+            return;
+        }
+        // An encrypted account may be the one in use; leave the config alone.
+        LiveOwner::Unsure => {}
+        LiveOwner::Unknown => {
+            if active.is_some() {
+                log::info!(
+                    "Codex is logged in to an account not in the vault; clearing active {:?}",
+                    active
+                );
+                if let Err(e) = clear_active_account() {
+                    log::warn!("Failed to clear the active account: {}", e);
+                }
+            }
+        }
+    }
+
+    let settings = load_settings();
+    if !settings.watch_codex_logins {
+        return;
+    }
+    let imported_as = if settings.auto_import_logins {
+        match import_login(&live, None).and_then(|name| {
+            write_active_account(&name)?;
+            Ok(name)
+        }) {
+            Ok(name) => Some(name),
+            Err(e) => {
+                log::warn!("Failed to auto-import login: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let email = live["email"].as_str().map(str::to_string);
+    match &imported_as {
+        Some(name) => notify(
+            app,
+            &tr!("notify.login_imported.title"),
+            &tr!("notify.login_imported.body", name = name),
+        ),
+        None => notify(
+            app,
+            &tr!("notify.new_login.title"),
+            &tr!(
+                "notify.new_login.body",
+                who = email
+                    .clone()
+                    .unwrap_or_else(|| tr!("notify.new_login.current")),
+            ),
+        ),
+    }
+    let _ = app.emit(
+        "new-login-detected",
+        NewLoginPayload {
+            fingerprint,
+            email,
+            imported_as: imported_as.clone(),
+        },
+    );
+
+    if imported_as.is_some() {
+        let app_for_closure = app.clone();
+        let _ = app.run_on_main_thread(move || {
+            let _ = update_tray(&app_for_closure);
+        });
+    }
+}
+
+/// Adds the login Codex is currently using as account `name` and makes it
+/// active; the answer to a `new-login-detected` event.
+#[tauri::command]
+pub fn import_live_login(app: AppHandle, name: Option<String>) -> Result<String, String> {
+    let live = read_live_auth().ok_or("No readable Codex login to import")?;
+    let fingerprint = credential_fingerprint(&live).ok_or("The Codex login has no credentials")?;
+    if let LiveOwner::Account(existing) = live_owner(&fingerprint) {
+        return Err(format!("This login is already saved as '{}'", existing));
+    }
+    let name = import_login(&live, name)?;
+    write_active_account(&name)?;
+    let _ = update_tray(&app);
+    Ok(name)
+}
+
+/// Watches the directory holding the live Codex auth file, keeping the
+/// active account in sync with it and reporting logins that aren't in the
+/// vault. Also checks once at startup, for changes made while the app wasn't
+/// running.
+///
+/// Always started; reporting and importing unknown logins only happen while
+/// `watch_codex_logins` is on, so the setting can be toggled without
+/// restarting the app.
+pub fn start_login_watcher<R: Runtime>(app: AppHandle<R>) {
+    std::thread::spawn(move || {
+        let auth_path = live_auth_path();
+        let Some(watch_target) = auth_path.parent().map(|p| p.to_path_buf()) else {
+            return;
+        };
+
+        let (tx, rx) = channel();
+        let mut watcher = match RecommendedWatcher::new(tx, NotifyConfig::default()) {
+            Ok(w) => w,
+            Err(e) => {
+                log::warn!("Failed to create login watcher: {:?}", e);
+                return;
+            }
+        };
+        if let Err(e) = watcher.watch(&watch_target, RecursiveMode::NonRecursive) {
+            log::warn!("Failed to watch Codex auth dir: {:?}", e);
+            return;
+        }
+
+        let mut last_seen = None;
+        reconcile_live_auth(&app, &mut last_seen);
+        loop {
+            match rx.recv() {
+                Ok(Ok(event)) => {
+                    if (event.kind.is_modify() || event.kind.is_create())
+                        && event
+                            .paths
+                            .iter()
+                            .any(|p| p.file_name() == auth_path.file_name())
+                    {
+                        reconcile_live_auth(&app, &mut last_seen);
+                    }
+                }
+                Ok(Err(e)) => log::warn!("Login watch error: {:?}", e),
+                Err(_) => break,
+            }
+        }
+    });
+}
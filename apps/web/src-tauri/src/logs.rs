@@ -0,0 +1,152 @@
+//! Persistent logs, so problems in a bundled app (where stderr goes nowhere)
+//! can be attached to bug reports.
+//!
+//! Everything logged through `log` goes to `<app log dir>/codex-switch.log`,
+//! which is rotated at `MAX_LOG_BYTES` keeping the last `KEEP_LOG_FILES`.
+//! `export_support_bundle` zips those logs with the effective configuration.
+
+use std::fs;
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tauri::{AppHandle, Manager, Runtime};
+use tauri_plugin_log::{RotationStrategy, Target, TargetKind};
+
+use crate::accounts::write_private;
+use crate::settings::effective_config;
+
+const LOG_FILE_NAME: &str = "codex-switch";
+const MAX_LOG_BYTES: u128 = 1024 * 1024;
+const KEEP_LOG_FILES: usize = 5;
+
+/// Lines returned by `get_recent_logs` when the caller doesn't say.
+const DEFAULT_LOG_LINES: usize = 200;
+
+pub fn plugin<R: Runtime>() -> tauri::plugin::TauriPlugin<R> {
+    let level = if cfg!(debug_assertions) {
+        log::LevelFilter::Debug
+    } else {
+        log::LevelFilter::Info
+    };
+    tauri_plugin_log::Builder::default()
+        .level(level)
+        .targets([
+            Target::new(TargetKind::Stdout),
+            Target::new(TargetKind::LogDir {
+                file_name: Some(LOG_FILE_NAME.to_string()),
+            }),
+        ])
+        .max_file_size(MAX_LOG_BYTES)
+        .rotation_strategy(RotationStrategy::KeepSome(KEEP_LOG_FILES))
+        .build()
+}
+
+fn log_dir<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    app.path()
+        .app_log_dir()
+        .map_err(|e| format!("Failed to locate log folder: {}", e))
+}
+
+/// Log files newest first: the live one, then rotated ones by age.
+fn log_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<(std::time::SystemTime, PathBuf)> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| {
+                    p.extension().is_some_and(|ext| ext == "log")
+                        && p.file_name()
+                            .and_then(|n| n.to_str())
+                            .is_some_and(|n| n.starts_with(LOG_FILE_NAME))
+                })
+                .map(|p| {
+                    let modified = fs::metadata(&p)
+                        .and_then(|m| m.modified())
+                        .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
+                    (modified, p)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort_by(|a, b| b.0.cmp(&a.0));
+    files.into_iter().map(|(_, p)| p).collect()
+}
+
+/// The last `lines` log lines, oldest first, reaching into rotated files
+/// when the live one is shorter.
+#[tauri::command]
+pub fn get_recent_logs(app: AppHandle, lines: Option<usize>) -> Result<Vec<String>, String> {
+    let wanted = lines.unwrap_or(DEFAULT_LOG_LINES);
+    let mut collected: Vec<String> = Vec::new();
+    for path in log_files(&log_dir(&app)?) {
+        if collected.len() >= wanted {
+            break;
+        }
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.to_string_lossy(), e))?;
+        let older: Vec<String> = content.lines().map(str::to_string).collect();
+        let take = (wanted - collected.len()).min(older.len());
+        let mut chunk = older[older.len() - take..].to_vec();
+        chunk.append(&mut collected);
+        collected = chunk;
+    }
+    Ok(collected)
+}
+
+/// Opens the log folder in the system file manager.
+#[tauri::command]
+pub fn open_log_folder(app: AppHandle) -> Result<(), String> {
+    let dir = log_dir(&app)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create log folder: {}", e))?;
+    let opener = if cfg!(target_os = "macos") {
+        "open"
+    } else if cfg!(windows) {
+        "explorer"
+    } else {
+        "xdg-open"
+    };
+    Command::new(opener)
+        .arg(&dir)
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Failed to open {}: {}", dir.to_string_lossy(), e))
+}
+
+/// Writes a zip to attach to bug reports at `dest`: `effective_config.json`
+/// (see `effective_config`) and every log file under `logs/`. Returns the
+/// names of the files included.
+#[tauri::command]
+pub fn export_support_bundle(app: AppHandle, dest: String) -> Result<Vec<String>, String> {
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    let mut included = Vec::new();
+    let mut add = |name: String, bytes: &[u8]| -> Result<(), String> {
+        zip.start_file(name.as_str(), options)
+            .map_err(|e| format!("Failed to add {}: {}", name, e))?;
+        zip.write_all(bytes)
+            .map_err(|e| format!("Failed to add {}: {}", name, e))?;
+        included.push(name);
+        Ok(())
+    };
+
+    let config = serde_json::to_vec_pretty(&effective_config())
+        .map_err(|e| format!("Failed to serialize configuration: {}", e))?;
+    add("effective_config.json".to_string(), &config)?;
+    for path in log_files(&log_dir(&app)?) {
+        let Some(name) = path.file_name().map(|n| n.to_string_lossy().into_owned()) else {
+            continue;
+        };
+        let bytes = fs::read(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.to_string_lossy(), e))?;
+        add(format!("logs/{}", name), &bytes)?;
+    }
+
+    let archive = zip
+        .finish()
+        .map_err(|e| format!("Failed to finish support bundle: {}", e))?
+        .into_inner();
+    write_private(Path::new(&dest), &archive)?;
+    Ok(included)
+}
@@ -0,0 +1,136 @@
+//! Per-account presentation metadata, stored in `accounts/<name>/meta.json`.
+//!
+//! The file predates this module (imports write `notes` into it), so keys
+//! this version doesn't know about are kept when it is rewritten. Living in
+//! the account directory means it follows renames and shows up in backups
+//! and vault exports without extra handling.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+use crate::accounts::{account_dir, validate_account_name, write_private};
+use crate::i18n::tr;
+use crate::update_tray;
+
+const META_FILE: &str = "meta.json";
+
+/// Longest display name accepted; the tray has little room.
+const MAX_DISPLAY_NAME_LEN: usize = 64;
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct AccountMetadata {
+    /// Shown in the tray instead of the directory name.
+    pub display_name: Option<String>,
+    /// Tray prefix; takes precedence over the `labels` setting.
+    pub emoji: Option<String>,
+    /// `#rrggbb`, for the manager window (tray menus can't color text).
+    pub color: Option<String>,
+    pub tags: Vec<String>,
+    /// Pinned accounts are listed first in the tray.
+    pub pinned: bool,
+    pub notes: Option<String>,
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl AccountMetadata {
+    /// The display name, or `name` when none is set.
+    pub fn title<'a>(&'a self, name: &'a str) -> &'a str {
+        self.display_name.as_deref().unwrap_or(name)
+    }
+}
+
+fn meta_path(name: &str) -> PathBuf {
+    account_dir(name).join(META_FILE)
+}
+
+/// Missing or unreadable metadata counts as empty.
+pub fn load_metadata(name: &str) -> AccountMetadata {
+    fs::read_to_string(meta_path(name))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Metadata for every account in `accounts`; ones without any are omitted.
+pub fn load_all(accounts: &[String]) -> HashMap<String, AccountMetadata> {
+    accounts
+        .iter()
+        .filter(|name| meta_path(name).is_file())
+        .map(|name| (name.clone(), load_metadata(name)))
+        .collect()
+}
+
+/// Trims text fields, turning blank ones into `None`.
+fn clean(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn is_hex_color(color: &str) -> bool {
+    color.len() == 7 && color.starts_with('#') && color[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Replaces the metadata of `name`. Blank fields are cleared, tags are
+/// trimmed and deduplicated, and keys written by other tools are kept.
+#[tauri::command]
+pub fn set_account_metadata(
+    app: AppHandle,
+    name: String,
+    metadata: AccountMetadata,
+) -> Result<AccountMetadata, String> {
+    validate_account_name(&name)?;
+    if !account_dir(&name).is_dir() {
+        return Err(tr!("error.account_not_found", name = name));
+    }
+
+    let display_name = clean(metadata.display_name);
+    if let Some(display) = &display_name {
+        if display.chars().count() > MAX_DISPLAY_NAME_LEN {
+            return Err(format!(
+                "Display name '{}' is longer than {} characters",
+                display, MAX_DISPLAY_NAME_LEN
+            ));
+        }
+    }
+    let color = clean(metadata.color);
+    if let Some(color) = color.as_deref().filter(|c| !is_hex_color(c)) {
+        return Err(format!("Color '{}' is not of the form #rrggbb", color));
+    }
+    let mut tags: Vec<String> = metadata
+        .tags
+        .into_iter()
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .collect();
+    tags.sort();
+    tags.dedup();
+
+    let updated = AccountMetadata {
+        display_name,
+        emoji: clean(metadata.emoji),
+        color,
+        tags,
+        pinned: metadata.pinned,
+        notes: clean(metadata.notes),
+        extra: load_metadata(&name).extra,
+    };
+    let body = serde_json::to_vec_pretty(&updated)
+        .map_err(|e| format!("Failed to serialize metadata: {}", e))?;
+    write_private(&meta_path(&name), &body)?;
+    let _ = update_tray(&app);
+    Ok(updated)
+}
+
+#[tauri::command]
+pub fn get_account_metadata(name: String) -> Result<AccountMetadata, String> {
+    validate_account_name(&name)?;
+    if !account_dir(&name).is_dir() {
+        return Err(tr!("error.account_not_found", name = name));
+    }
+    Ok(load_metadata(&name))
+}
@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, Runtime, State};
+
+use crate::accounts::validate_account_name;
+use crate::usage::UsageLimits;
+use crate::{update_tray, AppState, CacheEntry};
+
+/// Debug-only usage overrides layered over `usage_cache.json` when the tray is
+/// built. Nothing here is ever written to disk, and release builds compile
+/// none of it.
+#[derive(Default)]
+pub struct MockUsage {
+    resets: Mutex<HashMap<String, i64>>,
+}
+
+/// Patches mocked values into a freshly loaded state.
+pub fn apply<R: Runtime>(app: &AppHandle<R>, state: &mut AppState) {
+    let Some(mocks) = app.try_state::<MockUsage>() else {
+        return;
+    };
+    let Ok(resets) = mocks.resets.lock() else {
+        return;
+    };
+    for (name, resets_at) in resets.iter() {
+        let entry = state
+            .usage_cache
+            .entry(name.clone())
+            .or_insert_with(|| CacheEntry {
+                limits: UsageLimits::default(),
+                updated_at: None,
+                raw: None,
+            });
+        entry
+            .limits
+            .limit_5h
+            .get_or_insert_with(Default::default)
+            .resets_at = Some(*resets_at as f64);
+    }
+}
+
+/// Forces the 5h window's `resets_at` (unix seconds) for `name`; `None` clears it.
+#[tauri::command]
+pub fn set_mock_reset(
+    app: AppHandle,
+    mocks: State<'_, MockUsage>,
+    name: String,
+    resets_at: Option<i64>,
+) -> Result<(), String> {
+    validate_account_name(&name)?;
+
+    {
+        let mut resets = mocks.resets.lock().map_err(|_| "Mock state poisoned")?;
+        match resets_at {
+            Some(ts) => resets.insert(name, ts),
+            None => resets.remove(&name),
+        };
+    }
+
+    update_tray(&app).map_err(|e| e.to_string())
+}
@@ -0,0 +1,314 @@
+//! Adding an account by signing in, with the OAuth device-code flow (RFC 8628).
+//!
+//! `start_account_login` requests a user code and returns it (also emitted as
+//! `login-pending`) for the UI to show next to the verification link. The
+//! token endpoint is then polled in the background; the outcome is emitted
+//! as `login-success` or `login-failed` and shown as a notification, and the
+//! tray lists logins still waiting for the user.
+//!
+//! The account directory is assembled under a dot-prefixed name, which the
+//! vault ignores, and renamed into place once complete.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use crate::accounts::{account_dir, accounts_dir, validate_account_name, write_private};
+use crate::health::{jwt_claims, now_secs};
+use crate::i18n::tr;
+use crate::polling::{http_client, poll_account};
+use crate::settings::{load_settings, LoginSettings};
+use crate::{notify, secrets, update_tray, with_watcher_paused};
+
+const DEVICE_CODE_GRANT: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+/// Used when the server doesn't say how often to poll.
+const DEFAULT_POLL_INTERVAL: u64 = 5;
+
+/// Added to the interval whenever the server answers `slow_down`.
+const SLOW_DOWN_STEP: u64 = 5;
+
+#[derive(serde::Deserialize)]
+struct DeviceAuthorization {
+    device_code: String,
+    user_code: String,
+    verification_uri: String,
+    #[serde(default)]
+    verification_uri_complete: Option<String>,
+    expires_in: u64,
+    #[serde(default)]
+    interval: Option<u64>,
+}
+
+/// What the UI needs to send the user to the sign-in page.
+#[derive(serde::Serialize, Clone)]
+pub struct LoginPending {
+    name: String,
+    user_code: String,
+    verification_uri: String,
+    verification_uri_complete: Option<String>,
+    expires_at: i64,
+}
+
+#[derive(serde::Serialize, Clone)]
+struct LoginFailed {
+    name: String,
+    error: String,
+}
+
+/// Logins waiting for the user, by account name. Removing one cancels it.
+#[derive(Default)]
+pub struct LoginFlows(Mutex<BTreeMap<String, LoginPending>>);
+
+/// Logins in progress, for the tray.
+pub fn pending_logins<R: Runtime>(app: &AppHandle<R>) -> Vec<LoginPending> {
+    app.try_state::<LoginFlows>()
+        .and_then(|flows| flows.0.lock().ok().map(|f| f.values().cloned().collect()))
+        .unwrap_or_default()
+}
+
+impl LoginPending {
+    /// Tray text: "Signing in: work (code ABCD-EFGH)".
+    pub fn label(&self) -> String {
+        tr!("tray.signing_in", name = self.name, code = self.user_code)
+    }
+}
+
+fn is_pending<R: Runtime>(app: &AppHandle<R>, name: &str) -> bool {
+    app.try_state::<LoginFlows>()
+        .is_some_and(|flows| flows.0.lock().is_ok_and(|f| f.contains_key(name)))
+}
+
+fn finish<R: Runtime>(app: &AppHandle<R>, name: &str) {
+    if let Some(flows) = app.try_state::<LoginFlows>() {
+        if let Ok(mut flows) = flows.0.lock() {
+            flows.remove(name);
+        }
+    }
+}
+
+async fn request_device_code(
+    client: &reqwest::Client,
+    login: &LoginSettings,
+) -> Result<DeviceAuthorization, String> {
+    client
+        .post(&login.device_authorization_endpoint)
+        .form(&[
+            ("client_id", login.client_id.as_str()),
+            ("scope", login.scope.as_str()),
+        ])
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Device authorization failed: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Invalid device authorization response: {}", e))
+}
+
+/// One token request: `Ok(Some(tokens))` once approved, `Ok(None)` while the
+/// user hasn't finished, `Err` when the flow is over.
+async fn poll_token(
+    client: &reqwest::Client,
+    login: &LoginSettings,
+    device_code: &str,
+    interval: &mut u64,
+) -> Result<Option<serde_json::Value>, String> {
+    let response = client
+        .post(&login.token_endpoint)
+        .form(&[
+            ("grant_type", DEVICE_CODE_GRANT),
+            ("device_code", device_code),
+            ("client_id", login.client_id.as_str()),
+        ])
+        .send()
+        .await
+        .map_err(|e| format!("Token request failed: {}", e))?;
+    let ok = response.status().is_success();
+    let body: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Invalid token response: {}", e))?;
+    if ok {
+        return Ok(Some(body));
+    }
+    match body["error"].as_str() {
+        Some("authorization_pending") => Ok(None),
+        Some("slow_down") => {
+            *interval += SLOW_DOWN_STEP;
+            Ok(None)
+        }
+        Some("access_denied") => Err("Sign-in was declined".to_string()),
+        Some("expired_token") => Err("The sign-in code expired".to_string()),
+        Some(other) => Err(format!("Sign-in failed: {}", other)),
+        None => Err("Sign-in failed: unexpected token response".to_string()),
+    }
+}
+
+/// A vault record in the CLI's format (see `logins::account_record`).
+fn account_record(name: &str, tokens: &serde_json::Value) -> serde_json::Value {
+    let mut record = serde_json::json!({
+        "name": name,
+        "created_at": chrono::Local::now().to_rfc3339(),
+        "type": "oauth",
+        "tokens": {
+            "access_token": tokens["access_token"],
+            "refresh_token": tokens["refresh_token"],
+            "id_token": tokens["id_token"],
+        },
+    });
+    if let Some(account_id) = tokens["account_id"].as_str() {
+        record["tokens"]["account_id"] = account_id.into();
+    }
+    let email = tokens["id_token"]
+        .as_str()
+        .and_then(jwt_claims)
+        .and_then(|claims| claims["email"].as_str().map(str::to_string));
+    if let Some(email) = email {
+        record["email"] = email.into();
+    }
+    record
+}
+
+/// Writes the account under a hidden name, then renames it into place.
+fn create_account(name: &str, record: &serde_json::Value) -> Result<(), String> {
+    let staging = accounts_dir().join(format!(".login-{}", name));
+    let _ = fs::remove_dir_all(&staging);
+    let body = serde_json::to_vec_pretty(record)
+        .map_err(|e| format!("Failed to serialize credentials: {}", e))?;
+    write_private(&staging.join("auth.json"), &body)?;
+
+    let dest = account_dir(name);
+    let result = if dest.exists() {
+        Err(tr!("error.account_exists", name = name))
+    } else {
+        fs::rename(&staging, &dest).map_err(|e| format!("Failed to create account: {}", e))
+    };
+    if result.is_err() {
+        let _ = fs::remove_dir_all(&staging);
+    }
+    result
+}
+
+/// Polls until the user approves, declines, the code expires or the login
+/// is cancelled, then stores the account.
+async fn complete_login<R: Runtime>(
+    app: &AppHandle<R>,
+    login: &LoginSettings,
+    name: &str,
+    authorization: DeviceAuthorization,
+    expires_at: i64,
+) -> Result<(), String> {
+    let client = http_client()?;
+    let mut interval = authorization.interval.unwrap_or(DEFAULT_POLL_INTERVAL);
+    let tokens = loop {
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+        if !is_pending(app, name) {
+            return Err("Sign-in was cancelled".to_string());
+        }
+        if now_secs() >= expires_at {
+            return Err("The sign-in code expired".to_string());
+        }
+        if let Some(tokens) =
+            poll_token(&client, login, &authorization.device_code, &mut interval).await?
+        {
+            break tokens;
+        }
+    };
+    if tokens["access_token"].as_str().is_none() {
+        return Err("Sign-in returned no access token".to_string());
+    }
+
+    with_watcher_paused(app, || -> Result<(), String> {
+        create_account(name, &account_record(name, &tokens))?;
+        if load_settings().keychain_secrets {
+            secrets::migrate_account(name)?;
+        }
+        Ok(())
+    })?;
+    // The account exists either way; limits show up on the next poll otherwise.
+    if let Err(e) = poll_account(name).await {
+        log::warn!("Initial usage fetch for '{}' failed: {}", name, e);
+    }
+    Ok(())
+}
+
+/// Starts signing in a new account called `name` and returns the code the
+/// user has to enter. The rest of the flow runs in the background.
+#[tauri::command]
+pub async fn start_account_login(app: AppHandle, name: String) -> Result<LoginPending, String> {
+    validate_account_name(&name)?;
+    if account_dir(&name).exists() {
+        return Err(tr!("error.account_exists", name = name));
+    }
+    if is_pending(&app, &name) {
+        return Err(format!("A sign-in for '{}' is already in progress", name));
+    }
+
+    let login = load_settings().login;
+    let authorization = request_device_code(&http_client()?, &login).await?;
+    let pending = LoginPending {
+        name: name.clone(),
+        user_code: authorization.user_code.clone(),
+        verification_uri: authorization.verification_uri.clone(),
+        verification_uri_complete: authorization.verification_uri_complete.clone(),
+        expires_at: now_secs() + authorization.expires_in as i64,
+    };
+    {
+        let flows = app
+            .try_state::<LoginFlows>()
+            .ok_or("Login state unavailable")?;
+        let mut flows = flows.0.lock().map_err(|_| "Login state poisoned")?;
+        if flows.contains_key(&name) {
+            return Err(format!("A sign-in for '{}' is already in progress", name));
+        }
+        flows.insert(name.clone(), pending.clone());
+    }
+    let _ = app.emit("login-pending", &pending);
+    let _ = update_tray(&app);
+
+    let expires_at = pending.expires_at;
+    let app_for_task = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let app = app_for_task;
+        let result = complete_login(&app, &login, &name, authorization, expires_at).await;
+        finish(&app, &name);
+        match result {
+            Ok(()) => {
+                let _ = app.emit("login-success", &name);
+                notify(
+                    &app,
+                    &tr!("notify.account_added.title"),
+                    &tr!("notify.account_added.body", name = name),
+                );
+            }
+            Err(error) => {
+                log::error!("Sign-in for '{}' failed: {}", name, error);
+                notify(
+                    &app,
+                    &tr!("notify.sign_in_failed.title"),
+                    &tr!("notify.sign_in_failed.body", name = name, error = error),
+                );
+                let _ = app.emit("login-failed", LoginFailed { name, error });
+            }
+        }
+        let app_for_closure = app.clone();
+        let _ = app.run_on_main_thread(move || {
+            let _ = update_tray(&app_for_closure);
+        });
+    });
+    Ok(pending)
+}
+
+/// Abandons a sign-in; its background poll stops at the next tick.
+#[tauri::command]
+pub fn cancel_account_login(app: AppHandle, name: String) -> Result<(), String> {
+    if !is_pending(&app, &name) {
+        return Err(format!("No sign-in for '{}' is in progress", name));
+    }
+    finish(&app, &name);
+    let _ = update_tray(&app);
+    Ok(())
+}
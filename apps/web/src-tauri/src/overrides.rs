@@ -0,0 +1,89 @@
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, Runtime, State};
+
+use crate::accounts::{account_dir, apply_credentials, validate_account_name};
+use crate::i18n::tr;
+use crate::session;
+use crate::{load_state, update_tray};
+
+/// Temporary "run as" accounts layered over the persisted `active_account`.
+///
+/// Only the live credentials are swapped; `config.json` is never touched, so
+/// popping the last override (or quitting) falls back to the persisted account.
+#[derive(Default)]
+pub struct OverrideStack(pub Mutex<Vec<String>>);
+
+impl OverrideStack {
+    pub fn current(&self) -> Option<String> {
+        self.0.lock().ok().and_then(|stack| stack.last().cloned())
+    }
+}
+
+/// The account whose credentials are live right now, if it's an override.
+pub fn active_override<R: Runtime>(app: &AppHandle<R>) -> Option<String> {
+    app.try_state::<OverrideStack>()
+        .and_then(|stack| stack.current())
+}
+
+/// Re-applies the persisted active account's credentials.
+pub fn restore_persisted() -> Result<Option<String>, String> {
+    let persisted = load_state().active_account;
+    if let Some(name) = &persisted {
+        apply_credentials(name)?;
+    }
+    Ok(persisted)
+}
+
+/// Makes `name`'s credentials live on top of the stack. Refused while a Codex
+/// session is running unless `force`.
+#[tauri::command]
+pub fn push_active_override(
+    app: AppHandle,
+    stack: State<'_, OverrideStack>,
+    name: String,
+    force: Option<bool>,
+) -> Result<(), String> {
+    validate_account_name(&name)?;
+    if !account_dir(&name).is_dir() {
+        return Err(tr!("error.account_not_found", name = name));
+    }
+    session::ensure_idle(&app, force.unwrap_or(false))?;
+
+    let mut stack = stack.0.lock().map_err(|_| "Override stack poisoned")?;
+    apply_credentials(&name)?;
+    stack.push(name);
+    drop(stack);
+
+    let _ = update_tray(&app);
+    Ok(())
+}
+
+/// Pops the top override and returns the account that is live afterwards.
+/// If the credentials underneath can't be applied, the override stays.
+/// Refused while a Codex session is running unless `force`.
+#[tauri::command]
+pub fn pop_active_override(
+    app: AppHandle,
+    stack: State<'_, OverrideStack>,
+    force: Option<bool>,
+) -> Result<Option<String>, String> {
+    let mut stack = stack.0.lock().map_err(|_| "Override stack poisoned")?;
+    if stack.is_empty() {
+        return Err("No active override to pop".to_string());
+    }
+    session::ensure_idle(&app, force.unwrap_or(false))?;
+
+    let below = stack.iter().rev().nth(1).cloned();
+    let now_active = match below {
+        Some(name) => {
+            apply_credentials(&name)?;
+            Some(name)
+        }
+        None => restore_persisted()?,
+    };
+    stack.pop();
+    drop(stack);
+
+    let _ = update_tray(&app);
+    Ok(now_active)
+}
@@ -0,0 +1,171 @@
+//! Where the vault and the live Codex credentials live on each platform.
+//!
+//! The Python CLI always uses `Path.home() / ".codex-accounts"`, so that stays
+//! the default everywhere; the app and the CLI must agree on one vault.
+
+use std::env;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+const VAULT_DIR_NAME: &str = ".codex-accounts";
+
+static CODEX_ROOT: OnceLock<(PathBuf, RootOrigin)> = OnceLock::new();
+
+/// Which rule `codex_root` took the vault location from.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RootOrigin {
+    /// `CODEX_ACCOUNTS_DIR`.
+    Env,
+    /// An existing `~/.codex-accounts`.
+    Home,
+    /// An existing `codex-accounts` under the platform config dir.
+    ConfigDir,
+    /// Nothing exists yet; `~/.codex-accounts` is where it will be.
+    Default,
+}
+
+/// `platform` (the OS lookup), else `$HOME`, else `%USERPROFILE%`, skipping
+/// empty values. `var` reads an environment variable.
+fn find_home(
+    platform: Option<PathBuf>,
+    var: impl Fn(&'static str) -> Option<OsString>,
+) -> Option<PathBuf> {
+    platform.filter(|p| !p.as_os_str().is_empty()).or_else(|| {
+        ["HOME", "USERPROFILE"]
+            .into_iter()
+            .filter_map(var)
+            .find(|v| !v.is_empty())
+            .map(PathBuf::from)
+    })
+}
+
+/// The user's home directory: `$HOME` on macOS/Linux, the profile folder
+/// (`USERPROFILE`) on Windows, matching Python's `Path.home()`.
+///
+/// If the OS lookup fails, the environment variables are used directly, and
+/// failing those the temp directory, each with a logged warning. Never the
+/// current directory.
+fn home_dir() -> PathBuf {
+    let platform = dirs::home_dir();
+    let found_by_os = platform.is_some();
+    match find_home(platform, env::var_os) {
+        Some(home) => {
+            if !found_by_os {
+                log::warn!(
+                    "OS home lookup failed; using {} from the environment",
+                    home.to_string_lossy()
+                );
+            }
+            home
+        }
+        None => {
+            let fallback = env::temp_dir();
+            log::warn!(
+                "No home directory (HOME/USERPROFILE unset); using {}",
+                fallback.to_string_lossy()
+            );
+            fallback
+        }
+    }
+}
+
+/// The vault location given the `CODEX_ACCOUNTS_DIR` value, the home
+/// directory and the platform config dir, and the rule that chose it; see
+/// `codex_root`.
+pub fn resolve_root(
+    env_dir: Option<String>,
+    home: &Path,
+    config: Option<PathBuf>,
+) -> (PathBuf, RootOrigin) {
+    if let Some(dir) = env_dir.filter(|d| !d.is_empty()) {
+        return (PathBuf::from(dir), RootOrigin::Env);
+    }
+    let home_vault = home.join(VAULT_DIR_NAME);
+    if home_vault.is_dir() {
+        return (home_vault, RootOrigin::Home);
+    }
+    config
+        .map(|config| config.join("codex-accounts"))
+        .filter(|dir| dir.is_dir())
+        .map_or((home_vault, RootOrigin::Default), |dir| {
+            (dir, RootOrigin::ConfigDir)
+        })
+}
+
+fn root() -> &'static (PathBuf, RootOrigin) {
+    CODEX_ROOT.get_or_init(|| {
+        resolve_root(
+            env::var("CODEX_ACCOUNTS_DIR").ok(),
+            &home_dir(),
+            dirs::config_dir(),
+        )
+    })
+}
+
+/// The accounts vault, in order of preference:
+///
+/// 1. `CODEX_ACCOUNTS_DIR`, if set and non-empty;
+/// 2. `~/.codex-accounts`, if it exists (shared with the CLI);
+/// 3. `codex-accounts` under the platform config dir (`%APPDATA%`,
+///    `$XDG_CONFIG_HOME` or `~/.config`, `~/Library/Application Support`),
+///    if it exists;
+/// 4. `~/.codex-accounts`.
+///
+/// Resolved on first use and fixed for the life of the process. The
+/// directory need not exist; callers treat a missing root as an empty vault.
+pub fn codex_root() -> PathBuf {
+    root().0.clone()
+}
+
+/// The rule `codex_root` was resolved by.
+pub fn codex_root_origin() -> RootOrigin {
+    root().1
+}
+
+/// The credentials file the Codex CLI actually reads (`~/.codex/auth.json`).
+///
+/// Honors `CODEX_LEGACY_AUTH_FILE` the same way the Python CLI does.
+pub fn live_auth_path() -> PathBuf {
+    if let Ok(path) = env::var("CODEX_LEGACY_AUTH_FILE") {
+        if !path.is_empty() {
+            return PathBuf::from(path);
+        }
+    }
+    home_dir().join(".codex").join("auth.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn env_override_wins() {
+        let home = Path::new("/nonexistent-home");
+        assert_eq!(
+            resolve_root(Some("/vaults/test".to_string()), home, None),
+            (PathBuf::from("/vaults/test"), RootOrigin::Env)
+        );
+        assert_eq!(
+            resolve_root(Some(String::new()), home, None),
+            (home.join(VAULT_DIR_NAME), RootOrigin::Default)
+        );
+    }
+
+    #[test]
+    fn home_falls_back_to_env_then_none() {
+        let os = Some(PathBuf::from("/home/os"));
+        assert_eq!(find_home(os, |_| None), Some(PathBuf::from("/home/os")));
+
+        let userprofile =
+            |name: &str| (name == "USERPROFILE").then(|| OsString::from(r"C:\Users\me"));
+        assert_eq!(
+            find_home(None, userprofile),
+            Some(PathBuf::from(r"C:\Users\me"))
+        );
+
+        let empty_home = |name: &str| (name == "HOME").then(OsString::new);
+        assert_eq!(find_home(None, empty_home), None);
+        assert_eq!(find_home(None, |_| None), None);
+    }
+}
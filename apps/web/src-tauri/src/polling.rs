@@ -0,0 +1,166 @@
+//! Background usage polling, so the tray stays current without the CLI.
+//!
+//! Each plaintext account's limits are fetched with its own token and merged
+//! into `usage_cache.json` in the CLI's format; the file watcher then picks up
+//! the change and rebuilds the tray. Encrypted accounts are skipped since
+//! their tokens can't be read here.
+
+use std::collections::HashMap;
+use std::fs;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Runtime};
+
+use crate::accounts::write_private;
+use crate::health::{now_secs, read_credentials};
+use crate::settings::{load_settings, PollingSettings};
+use crate::{codex_root, current_state};
+
+/// The token the CLI would use for `account`: its API key, else its access token.
+pub fn account_token(account: &serde_json::Value) -> Option<String> {
+    account["api_key"]
+        .as_str()
+        .or_else(|| account["tokens"]["access_token"].as_str())
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+}
+
+/// A random-enough offset in `0..=max_secs`, spreading polls from many
+/// installs without pulling in an RNG.
+fn jitter(max_secs: u64) -> Duration {
+    if max_secs == 0 {
+        return Duration::ZERO;
+    }
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() as u64)
+        .unwrap_or(0);
+    Duration::from_secs(nanos % (max_secs + 1))
+}
+
+async fn fetch_limits(
+    client: &reqwest::Client,
+    endpoint: &str,
+    token: &str,
+) -> Result<serde_json::Value, String> {
+    client
+        .get(endpoint)
+        .bearer_auth(token)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("request failed: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("invalid response: {}", e))
+}
+
+/// Whether the limits endpoint rejects `token` (401/403). `None` when the
+/// answer is unknown, e.g. the request itself failed.
+pub async fn token_rejected(client: &reqwest::Client, endpoint: &str, token: &str) -> Option<bool> {
+    let status = client
+        .get(endpoint)
+        .bearer_auth(token)
+        .send()
+        .await
+        .ok()?
+        .status();
+    Some(status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN)
+}
+
+/// Writes fresh limits into `usage_cache.json`, keeping every other entry.
+fn merge_into_cache(fetched: HashMap<String, serde_json::Value>) -> Result<(), String> {
+    let path = codex_root().join("usage_cache.json");
+    let mut cache: serde_json::Map<String, serde_json::Value> = fs::read_to_string(&path)
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default();
+
+    let now = now_secs();
+    let updated_at = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    for (name, limits) in fetched {
+        cache.insert(
+            name,
+            serde_json::json!({
+                "limits": limits,
+                "timestamp": now,
+                "updated_at": updated_at,
+            }),
+        );
+    }
+
+    let body = serde_json::to_vec_pretty(&cache)
+        .map_err(|e| format!("Failed to serialize usage cache: {}", e))?;
+    write_private(&path, &body)
+}
+
+pub fn http_client() -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))
+}
+
+/// Fetches one account's limits into the cache.
+pub async fn poll_account(name: &str) -> Result<(), String> {
+    let account =
+        read_credentials(name)?.ok_or_else(|| format!("'{}' has no readable credentials", name))?;
+    let token = account_token(&account).ok_or_else(|| format!("'{}' has no token", name))?;
+    let endpoint = load_settings().usage_polling.endpoint;
+    let limits = fetch_limits(&http_client()?, &endpoint, &token).await?;
+    merge_into_cache(HashMap::from([(name.to_string(), limits)]))
+}
+
+/// Fetches every readable account in `accounts` once and returns how many
+/// were updated.
+async fn poll_once(settings: &PollingSettings, accounts: Vec<String>) -> Result<usize, String> {
+    let client = http_client()?;
+
+    let mut fetched = HashMap::new();
+    for name in accounts {
+        let Ok(Some(account)) = read_credentials(&name) else {
+            continue;
+        };
+        let Some(token) = account_token(&account) else {
+            continue;
+        };
+        match fetch_limits(&client, &settings.endpoint, &token).await {
+            Ok(limits) => {
+                fetched.insert(name, limits);
+            }
+            Err(e) => log::warn!("Usage poll for '{}' failed: {}", name, e),
+        }
+    }
+
+    let count = fetched.len();
+    if count > 0 {
+        merge_into_cache(fetched)?;
+    }
+    Ok(count)
+}
+
+/// Polls on the configured interval (plus jitter) for the life of the app.
+/// Settings are re-read every cycle, so toggling polling needs no restart.
+pub fn start_usage_poller<R: Runtime>(app: AppHandle<R>) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let polling = load_settings().usage_polling;
+            let wait = Duration::from_secs(polling.interval_minutes.max(1) * 60)
+                + jitter(polling.jitter_seconds);
+            tokio::time::sleep(wait).await;
+
+            let settings = load_settings();
+            if settings.usage_polling.enabled && !settings.pause_automations {
+                let accounts = current_state(&app).accounts;
+                if let Err(e) = poll_once(&settings.usage_polling, accounts).await {
+                    log::warn!("Usage poll failed: {}", e);
+                }
+            }
+        }
+    });
+}
+
+/// Polls every account right now, regardless of the schedule.
+#[tauri::command]
+pub async fn refresh_usage(app: AppHandle) -> Result<usize, String> {
+    poll_once(&load_settings().usage_polling, current_state(&app).accounts).await
+}
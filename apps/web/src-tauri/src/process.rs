@@ -0,0 +1,111 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::paths::live_auth_path;
+
+/// Executable names that mean a Codex session may be using the live credentials.
+const CODEX_PROCESS_NAMES: &[&str] = &["codex", "codex.exe", "Codex", "Codex.exe"];
+
+/// `(pid, executable name)` for every running process, or `None` if this
+/// platform's process listing isn't available.
+#[cfg(unix)]
+fn list_processes() -> Option<Vec<(u32, String)>> {
+    let output = Command::new("ps")
+        .args(["-axo", "pid=,comm="])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    Some(
+        text.lines()
+            .filter_map(|line| {
+                let (pid, comm) = line.trim().split_once(char::is_whitespace)?;
+                // `comm` is a full path on macOS; keep just the executable name.
+                let name = comm.trim().rsplit('/').next()?.to_string();
+                Some((pid.parse().ok()?, name))
+            })
+            .collect(),
+    )
+}
+
+#[cfg(windows)]
+fn list_processes() -> Option<Vec<(u32, String)>> {
+    let output = Command::new("tasklist")
+        .args(["/FO", "CSV", "/NH"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    Some(
+        text.lines()
+            .filter_map(|line| {
+                let mut fields = line.split("\",\"").map(|f| f.trim_matches('"'));
+                let name = fields.next()?.to_string();
+                let pid = fields.next()?.parse().ok()?;
+                Some((pid, name))
+            })
+            .collect(),
+    )
+}
+
+#[cfg(not(any(unix, windows)))]
+fn list_processes() -> Option<Vec<(u32, String)>> {
+    None
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct CodexRunning {
+    pub running: bool,
+    pub reasons: Vec<String>,
+}
+
+/// Advisory lock a Codex session can hold next to the live credentials. It
+/// may contain the holder's pid; a lock whose pid is gone is stale.
+pub fn session_lock_path() -> PathBuf {
+    live_auth_path().with_file_name("session.lock")
+}
+
+/// Why the session lock counts as held, or `None` if it doesn't.
+fn lock_reason(processes: Option<&[(u32, String)]>) -> Option<String> {
+    let path = session_lock_path();
+    let content = fs::read_to_string(&path).ok()?;
+    let pid = content
+        .lines()
+        .next()
+        .and_then(|line| line.trim().parse::<u32>().ok());
+    match (pid, processes) {
+        (Some(pid), Some(processes)) if !processes.iter().any(|(p, _)| *p == pid) => None,
+        (Some(pid), _) => Some(format!("{} (pid {})", path.to_string_lossy(), pid)),
+        (None, _) => Some(path.to_string_lossy().into_owned()),
+    }
+}
+
+/// Best-effort check for a running Codex CLI/app or a held session lock.
+/// Falls back to "not running" when processes can't be listed and there is
+/// no lock.
+pub fn detect_codex() -> CodexRunning {
+    let own_pid = std::process::id();
+    let processes = list_processes();
+    let mut reasons: Vec<String> = processes
+        .iter()
+        .flatten()
+        .filter(|(pid, name)| *pid != own_pid && CODEX_PROCESS_NAMES.contains(&name.as_str()))
+        .map(|(pid, name)| format!("{} (pid {})", name, pid))
+        .collect();
+    reasons.extend(lock_reason(processes.as_deref()));
+
+    CodexRunning {
+        running: !reasons.is_empty(),
+        reasons,
+    }
+}
+
+#[tauri::command]
+pub fn codex_running() -> CodexRunning {
+    detect_codex()
+}
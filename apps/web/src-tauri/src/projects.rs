@@ -0,0 +1,129 @@
+//! Per-project accounts: directories mapped to the account to use inside them.
+//!
+//! Mappings live in `~/.codex-accounts/projects.json` as `{ "<dir>": "<account>" }`.
+//! When the CLI (over IPC) or the UI reports a working directory, the deepest
+//! mapped ancestor wins and the app switches to its account if it isn't
+//! active already.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Runtime};
+
+use crate::accounts::{account_dir, validate_account_name};
+use crate::i18n::tr;
+use crate::session::{self, SessionPolicy, SwitchOutcome};
+use crate::settings::load_settings;
+use crate::{codex_root, current_state, update_tray};
+
+pub fn projects_path() -> PathBuf {
+    codex_root().join("projects.json")
+}
+
+pub fn load_projects() -> BTreeMap<String, String> {
+    fs::read_to_string(projects_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_projects(projects: &BTreeMap<String, String>) -> Result<(), String> {
+    let path = projects_path();
+    let json = serde_json::to_string_pretty(projects)
+        .map_err(|e| format!("Failed to serialize projects: {}", e))?;
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, json).map_err(|e| format!("Failed to write projects: {}", e))?;
+    fs::rename(&tmp, &path).map_err(|e| format!("Failed to save projects: {}", e))
+}
+
+/// Resolves symlinks where possible so `~/src` and its real path match.
+fn normalize(path: &str) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path))
+}
+
+/// The account mapped to `cwd` or its nearest mapped ancestor.
+pub fn account_for(projects: &BTreeMap<String, String>, cwd: &Path) -> Option<String> {
+    projects
+        .iter()
+        .filter(|(dir, _)| cwd.starts_with(dir))
+        .max_by_key(|(dir, _)| Path::new(dir).components().count())
+        .map(|(_, account)| account.clone())
+}
+
+/// Points every mapping at `new` instead of `old`.
+pub fn rename_in_projects(old: &str, new: &str) -> Result<(), String> {
+    let mut projects = load_projects();
+    let mut changed = false;
+    for account in projects.values_mut().filter(|a| a.as_str() == old) {
+        *account = new.to_string();
+        changed = true;
+    }
+    if changed {
+        save_projects(&projects)?;
+    }
+    Ok(())
+}
+
+/// Switches to the account mapped to `cwd`. Returns the account switched to,
+/// or `None` when nothing is mapped, it is already active, automations are
+/// paused, or the switch was queued behind a running Codex session.
+pub fn switch_for_cwd<R: Runtime>(app: &AppHandle<R>, cwd: &str) -> Result<Option<String>, String> {
+    if load_settings().pause_automations {
+        return Ok(None);
+    }
+    let Some(account) = account_for(&load_projects(), &normalize(cwd)) else {
+        return Ok(None);
+    };
+    if current_state(app).active_account.as_deref() == Some(account.as_str()) {
+        return Ok(None);
+    }
+
+    if session::switch(app, &account, SessionPolicy::Queue)? == SwitchOutcome::Queued {
+        return Ok(None);
+    }
+    let app_for_closure = app.clone();
+    let _ = app.run_on_main_thread(move || {
+        let _ = update_tray(&app_for_closure);
+    });
+    Ok(Some(account))
+}
+
+/// Maps `path` (a directory) to `account`, replacing any existing mapping.
+#[tauri::command]
+pub fn set_project_account(path: String, account: String) -> Result<(), String> {
+    validate_account_name(&account)?;
+    if !account_dir(&account).is_dir() {
+        return Err(tr!("error.account_not_found", name = account));
+    }
+    let dir = normalize(&path);
+    if !dir.is_dir() {
+        return Err(format!("'{}' is not a directory", path));
+    }
+    let mut projects = load_projects();
+    projects.insert(dir.to_string_lossy().into_owned(), account);
+    save_projects(&projects)
+}
+
+#[tauri::command]
+pub fn remove_project_account(path: String) -> Result<(), String> {
+    let mut projects = load_projects();
+    let removed = projects.remove(&path).is_some()
+        || projects
+            .remove(normalize(&path).to_string_lossy().as_ref())
+            .is_some();
+    if !removed {
+        return Err(format!("No account is mapped to '{}'", path));
+    }
+    save_projects(&projects)
+}
+
+#[tauri::command]
+pub fn list_project_accounts() -> BTreeMap<String, String> {
+    load_projects()
+}
+
+/// Called by the UI when the working directory it shows changes.
+#[tauri::command]
+pub fn report_cwd(app: AppHandle, path: String) -> Result<Option<String>, String> {
+    switch_for_cwd(&app, &path)
+}
@@ -0,0 +1,203 @@
+//! First run and recovery from a damaged vault.
+//!
+//! Before the state is first read, `prepare` creates the vault layout if it
+//! is missing and moves any vault JSON file that no longer parses aside as
+//! `<file>.corrupt-<ts>`, restoring the newest readable copy from the vault
+//! backups when there is one. Once the app is up, `report` logs what
+//! happened and emits `state-error` per damaged file and `onboarding-needed`
+//! when there are no accounts yet; the UI can also ask with
+//! `get_vault_status`, since those events may fire before it listens.
+
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use crate::accounts::{accounts_dir, write_private};
+use crate::backup::{backups_root, new_timestamp};
+use crate::i18n::tr;
+use crate::schedule::vault_backups_dir;
+use crate::settings::parse_settings;
+use crate::{codex_root, notify, read_accounts};
+
+/// Vault files whose loss would silently reset something. The first three
+/// are part of vault backups; the usage cache is rewritten by the CLI.
+const CHECKED_FILES: [&str; 4] = [
+    "config.json",
+    "settings.json",
+    "projects.json",
+    "usage_cache.json",
+];
+
+/// A vault file that failed to parse at startup.
+#[derive(serde::Serialize, Clone)]
+pub struct StateError {
+    file: String,
+    error: String,
+    /// Where the unreadable file was moved, if it could be.
+    quarantined_to: Option<String>,
+    /// The vault backup a good copy came from, if any.
+    restored_from: Option<String>,
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct VaultStatus {
+    root: String,
+    /// The vault didn't exist and was created by this launch.
+    created: bool,
+    /// No accounts yet; the UI should offer to add or import one.
+    onboarding_needed: bool,
+    errors: Vec<StateError>,
+}
+
+/// What `prepare` found, kept for `report` and `get_vault_status`.
+#[derive(Default)]
+pub struct Recovery {
+    created: bool,
+    errors: Vec<StateError>,
+}
+
+/// Creates the vault root with its `accounts/` and `backups/` folders.
+/// Returns whether the root had to be created.
+pub fn ensure_vault() -> Result<bool, String> {
+    let created = !codex_root().is_dir();
+    for dir in [accounts_dir(), backups_root()] {
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.to_string_lossy(), e))?;
+    }
+    Ok(created)
+}
+
+/// Renames `path` to `<name>.corrupt-<ts>` next to it.
+fn quarantine(path: &Path) -> Result<PathBuf, String> {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let dest = path.with_file_name(format!("{}.corrupt-{}", name, new_timestamp()));
+    fs::rename(path, &dest)
+        .map_err(|e| format!("Failed to move {} aside: {}", path.to_string_lossy(), e))?;
+    Ok(dest)
+}
+
+/// `file` from the newest vault backup that has a copy that parses.
+fn latest_good_copy(file: &str) -> Option<(PathBuf, Vec<u8>)> {
+    let mut archives: Vec<PathBuf> = fs::read_dir(vault_backups_dir())
+        .ok()?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "zip"))
+        .collect();
+    // Archive names are UTC timestamps, so this is newest first.
+    archives.sort();
+    archives.reverse();
+    archives.into_iter().find_map(|path| {
+        let mut archive = zip::ZipArchive::new(fs::File::open(&path).ok()?).ok()?;
+        let mut bytes = Vec::new();
+        archive.by_name(file).ok()?.read_to_end(&mut bytes).ok()?;
+        serde_json::from_slice::<serde_json::Value>(&bytes).ok()?;
+        Some((path, bytes))
+    })
+}
+
+/// Why `file`'s `content` is unusable, if it is: not JSON, or for
+/// `settings.json` not something `load_settings` can read.
+fn parse_error(file: &str, content: &[u8]) -> Option<String> {
+    if file == "settings.json" {
+        return parse_settings(content).err();
+    }
+    serde_json::from_slice::<serde_json::Value>(content)
+        .err()
+        .map(|e| e.to_string())
+}
+
+/// Quarantines `file` if it exists but doesn't parse, then restores it from
+/// the vault backups where possible.
+fn check_file(file: &str) -> Option<StateError> {
+    let path = codex_root().join(file);
+    let content = fs::read(&path).ok()?;
+    let error = parse_error(file, &content)?;
+    let mut state_error = StateError {
+        file: file.to_string(),
+        error,
+        quarantined_to: None,
+        restored_from: None,
+    };
+    match quarantine(&path) {
+        Ok(dest) => state_error.quarantined_to = Some(dest.to_string_lossy().into_owned()),
+        // Left in place; the loaders treat it as absent and warn.
+        Err(e) => {
+            state_error.error = format!("{}; {}", state_error.error, e);
+            return Some(state_error);
+        }
+    }
+    if let Some((archive, bytes)) = latest_good_copy(file) {
+        if write_private(&path, &bytes).is_ok() {
+            state_error.restored_from = Some(archive.to_string_lossy().into_owned());
+        }
+    }
+    Some(state_error)
+}
+
+/// Runs before the state is first loaded: creates the vault if needed and
+/// repairs corrupt files. Logging isn't up yet, so findings are kept for
+/// `report`.
+pub fn prepare() -> Recovery {
+    let created = ensure_vault().unwrap_or(false);
+    let errors = CHECKED_FILES.iter().filter_map(|f| check_file(f)).collect();
+    Recovery { created, errors }
+}
+
+fn status<R: Runtime>(app: &AppHandle<R>) -> VaultStatus {
+    let recovery = app.try_state::<Recovery>();
+    VaultStatus {
+        root: codex_root().to_string_lossy().into_owned(),
+        created: recovery.as_ref().is_some_and(|r| r.created),
+        onboarding_needed: read_accounts().is_empty(),
+        errors: recovery.map(|r| r.errors.clone()).unwrap_or_default(),
+    }
+}
+
+/// Logs and announces what `prepare` found; called once from setup.
+pub fn report<R: Runtime>(app: &AppHandle<R>) {
+    let status = status(app);
+    if status.created {
+        log::info!("Created a new vault at {}", status.root);
+    }
+    for error in &status.errors {
+        match (&error.quarantined_to, &error.restored_from) {
+            (Some(moved), Some(backup)) => log::error!(
+                "{} was corrupt ({}); moved to {} and restored from {}",
+                error.file,
+                error.error,
+                moved,
+                backup
+            ),
+            (Some(moved), None) => log::error!(
+                "{} was corrupt ({}); moved to {}, no backup to restore",
+                error.file,
+                error.error,
+                moved
+            ),
+            _ => log::error!("{} is corrupt: {}", error.file, error.error),
+        }
+        let _ = app.emit("state-error", error);
+    }
+    if !status.errors.is_empty() {
+        let files: Vec<&str> = status.errors.iter().map(|e| e.file.as_str()).collect();
+        notify(
+            app,
+            &tr!("notify.repaired.title"),
+            &tr!("notify.repaired.body", files = files.join(", ")),
+        );
+    }
+    if status.onboarding_needed {
+        let _ = app.emit("onboarding-needed", &status);
+    }
+}
+
+/// Vault location, first-run state and anything repaired at startup.
+#[tauri::command]
+pub fn get_vault_status(app: AppHandle) -> VaultStatus {
+    status(&app)
+}
@@ -0,0 +1,91 @@
+//! Keeps "resets in …" countdowns current and reacts when a window resets.
+//!
+//! Timers are checked against the wall clock on a short tick rather than
+//! slept until the reset: a sleeping machine pauses the runtime's timers, and
+//! on wake every reset passed in the meantime is seen on the next tick.
+
+use std::time::Duration;
+use tauri::{AppHandle, Runtime};
+
+use crate::health::now_secs;
+use crate::i18n::{limit_name, tr};
+use crate::settings::load_settings;
+use crate::{current_state, mock, notify, render_tray, AppState};
+
+const RESET_TICK: Duration = Duration::from_secs(30);
+
+/// "1h 23m"-style time until `at`, to the minute.
+pub fn format_countdown(now: i64, at: i64) -> String {
+    let minutes = (at - now).max(0) / 60;
+    match (minutes / (24 * 60), minutes / 60 % 24, minutes % 60) {
+        (0, 0, 0) => tr!("countdown.soon"),
+        (0, 0, m) => tr!("countdown.minutes", m = m),
+        (0, h, m) => tr!("countdown.hours", h = h, m = m),
+        (d, h, _) => tr!("countdown.days", d = d, h = h),
+    }
+}
+
+/// `(account, window)` for every reset time in `(since, now]`.
+fn passed_resets(state: &AppState, since: i64, now: i64) -> Vec<(String, &'static str)> {
+    let mut passed = Vec::new();
+    for name in &state.accounts {
+        let Some(entry) = state.usage_cache.get(name) else {
+            continue;
+        };
+        for (window, limits) in [
+            ("5h", &entry.limits.limit_5h),
+            ("weekly", &entry.limits.limit_weekly),
+        ] {
+            let at = limits.as_ref().and_then(|w| w.reset_time());
+            if at.is_some_and(|at| since < at && at <= now) {
+                passed.push((name.clone(), window));
+            }
+        }
+    }
+    passed
+}
+
+/// Whether the tray currently shows any countdown.
+fn has_countdowns(state: &AppState, now: i64) -> bool {
+    state
+        .usage_cache
+        .values()
+        .any(|entry| entry.next_reset(now).is_some())
+}
+
+/// Rebuilds the tray when a countdown's minute changes or a window resets,
+/// notifying about resets when `notifications.reset_times` is on.
+pub fn start_reset_watcher<R: Runtime>(app: AppHandle<R>) {
+    tauri::async_runtime::spawn(async move {
+        let mut last = now_secs();
+        loop {
+            tokio::time::sleep(RESET_TICK).await;
+            let now = now_secs();
+            let mut state = current_state(&app);
+            mock::apply(&app, &mut state);
+
+            let passed = passed_resets(&state, last, now);
+            if load_settings().notifications.reset_times {
+                for (name, window) in &passed {
+                    notify(
+                        &app,
+                        &tr!("notify.window_reset.title"),
+                        &tr!(
+                            "notify.window_reset.body",
+                            name = state.title(name),
+                            limit = limit_name(window),
+                        ),
+                    );
+                }
+            }
+            let minute_changed = now / 60 != last / 60;
+            if !passed.is_empty() || (minute_changed && has_countdowns(&state, now)) {
+                let app_for_closure = app.clone();
+                let _ = app.run_on_main_thread(move || {
+                    let _ = render_tray(&app_for_closure);
+                });
+            }
+            last = now;
+        }
+    });
+}
@@ -0,0 +1,267 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, Runtime, State};
+
+use crate::accounts::{account_dir, validate_account_name};
+use crate::health::now_secs;
+use crate::i18n::tr;
+use crate::session::{self, SessionPolicy, SwitchOutcome};
+use crate::settings::{load_settings, save_settings};
+use crate::{account_usage, current_state, notify, update_tray, AppState};
+
+/// How often the rotation thread wakes to check for a stop request.
+const ROTATION_TICK: Duration = Duration::from_secs(1);
+
+/// A rotation due during a Codex session waits for it to end.
+const SESSION_POLICY: SessionPolicy = SessionPolicy::Queue;
+
+#[derive(serde::Serialize, Clone, Default)]
+pub struct RotationStatus {
+    running: bool,
+    interval_minutes: u64,
+    accounts: Vec<String>,
+    next_switch_at: Option<i64>,
+    last_switched_to: Option<String>,
+}
+
+/// Timed account rotation. Bumping `generation` stops the running thread.
+#[derive(Default)]
+pub struct Rotation {
+    generation: AtomicU64,
+    status: Mutex<RotationStatus>,
+}
+
+pub fn is_usable(state: &AppState, name: &str) -> bool {
+    account_dir(name).is_dir()
+        && account_usage(state, name).map_or(true, |(p5, pw)| p5.max(pw) < 100.0)
+}
+
+/// The account after `current` in `order`, wrapping around and skipping
+/// anything `usable` rejects. `None` if no other account qualifies.
+pub fn next_in_rotation(
+    order: &[String],
+    current: Option<&str>,
+    usable: impl Fn(&str) -> bool,
+) -> Option<String> {
+    let start = current
+        .and_then(|c| order.iter().position(|n| n == c))
+        .map_or(0, |i| i + 1);
+    (0..order.len())
+        .map(|offset| &order[(start + offset) % order.len()])
+        .find(|name| Some(name.as_str()) != current && usable(name))
+        .cloned()
+}
+
+fn rotate_once<R: Runtime>(app: &AppHandle<R>) {
+    let settings = load_settings();
+    if settings.pause_automations {
+        return;
+    }
+
+    let state = current_state(app);
+    let order = if settings.rotation.accounts.is_empty() {
+        state.accounts.clone()
+    } else {
+        settings.rotation.accounts.clone()
+    };
+    let current = state.active_account.as_deref();
+    let Some(next) = next_in_rotation(&order, current, |n| is_usable(&state, n)) else {
+        return;
+    };
+
+    match session::switch(app, &next, SESSION_POLICY) {
+        Ok(SwitchOutcome::Queued) => {
+            log::info!("Rotation to '{}' waits for Codex to exit", next);
+            notify(
+                app,
+                &tr!("notify.switch_queued.title"),
+                &tr!("notify.switch_queued.body", name = next),
+            );
+        }
+        Ok(SwitchOutcome::Switched) => {
+            if let Some(rotation) = app.try_state::<Rotation>() {
+                if let Ok(mut status) = rotation.status.lock() {
+                    status.last_switched_to = Some(next.clone());
+                }
+            }
+            notify(
+                app,
+                &tr!("notify.rotated.title"),
+                &tr!("notify.switched.now_using", name = next),
+            );
+            let app_for_closure = app.clone();
+            let _ = app.run_on_main_thread(move || {
+                let _ = update_tray(&app_for_closure);
+            });
+        }
+        Err(e) => log::error!("Rotation to '{}' failed: {}", next, e),
+    }
+}
+
+fn spawn_rotation<R: Runtime>(app: AppHandle<R>, generation: u64) {
+    std::thread::spawn(move || loop {
+        let interval = load_settings().rotation.interval_minutes.max(1) * 60;
+        let deadline = now_secs() + interval as i64;
+        if let Some(rotation) = app.try_state::<Rotation>() {
+            if let Ok(mut status) = rotation.status.lock() {
+                status.next_switch_at = Some(deadline);
+            }
+        }
+
+        while now_secs() < deadline {
+            std::thread::sleep(ROTATION_TICK);
+            let current = app
+                .try_state::<Rotation>()
+                .map(|r| r.generation.load(Ordering::SeqCst));
+            if current != Some(generation) {
+                return;
+            }
+        }
+        rotate_once(&app);
+    });
+}
+
+/// Restarts rotation from persisted settings; used at startup.
+pub fn resume_if_enabled<R: Runtime>(app: &AppHandle<R>) {
+    if load_settings().rotation.enabled {
+        if let Some(rotation) = app.try_state::<Rotation>() {
+            start(app, &rotation);
+        }
+    }
+}
+
+fn start<R: Runtime>(app: &AppHandle<R>, rotation: &Rotation) {
+    let generation = rotation.generation.fetch_add(1, Ordering::SeqCst) + 1;
+    if let Ok(mut status) = rotation.status.lock() {
+        status.running = true;
+    }
+    spawn_rotation(app.clone(), generation);
+}
+
+fn halt(rotation: &Rotation) {
+    rotation.generation.fetch_add(1, Ordering::SeqCst);
+    if let Ok(mut status) = rotation.status.lock() {
+        status.running = false;
+    }
+}
+
+/// Starts or stops rotation to match `enabled`; does nothing if it already does.
+pub fn set_running<R: Runtime>(app: &AppHandle<R>, enabled: bool) {
+    let Some(rotation) = app.try_state::<Rotation>() else {
+        return;
+    };
+    let running = rotation.status.lock().is_ok_and(|s| s.running);
+    if enabled && !running {
+        start(app, &rotation);
+    } else if !enabled && running {
+        halt(&rotation);
+    }
+}
+
+fn snapshot(rotation: &Rotation) -> RotationStatus {
+    let settings = load_settings().rotation;
+    let mut status = rotation
+        .status
+        .lock()
+        .map(|s| s.clone())
+        .unwrap_or_default();
+    status.interval_minutes = settings.interval_minutes;
+    status.accounts = settings.accounts;
+    if !status.running {
+        status.next_switch_at = None;
+    }
+    status
+}
+
+#[tauri::command]
+pub fn start_rotation(
+    app: AppHandle,
+    rotation: State<'_, Rotation>,
+    interval_minutes: Option<u64>,
+    accounts: Option<Vec<String>>,
+) -> Result<RotationStatus, String> {
+    let mut settings = load_settings();
+    if let Some(minutes) = interval_minutes {
+        if minutes == 0 {
+            return Err("Rotation interval must be at least one minute".to_string());
+        }
+        settings.rotation.interval_minutes = minutes;
+    }
+    if let Some(accounts) = accounts {
+        for name in &accounts {
+            validate_account_name(name)?;
+        }
+        settings.rotation.accounts = accounts;
+    }
+    settings.rotation.enabled = true;
+    save_settings(&settings)?;
+
+    start(&app, &rotation);
+    Ok(snapshot(&rotation))
+}
+
+#[tauri::command]
+pub fn stop_rotation(rotation: State<'_, Rotation>) -> Result<RotationStatus, String> {
+    let mut settings = load_settings();
+    settings.rotation.enabled = false;
+    save_settings(&settings)?;
+
+    halt(&rotation);
+    Ok(snapshot(&rotation))
+}
+
+#[tauri::command]
+pub fn rotation_status(rotation: State<'_, Rotation>) -> RotationStatus {
+    snapshot(&rotation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::CodexRunning;
+    use crate::session::{gate, Gate};
+
+    #[test]
+    fn tick_during_session_is_queued() {
+        let running = CodexRunning {
+            running: true,
+            reasons: vec!["codex process".to_string()],
+        };
+        assert!(matches!(gate(SESSION_POLICY, Some(running)), Gate::Queue));
+        assert!(matches!(gate(SESSION_POLICY, None), Gate::Apply));
+    }
+
+    #[test]
+    fn rotation_wraps_and_skips_unusable_accounts() {
+        let order = ["a", "b", "c", "d"].map(str::to_string);
+        let all = |_: &str| true;
+        assert_eq!(next_in_rotation(&order, Some("b"), all), Some("c".into()));
+        assert_eq!(next_in_rotation(&order, Some("d"), all), Some("a".into()));
+
+        let not_a_or_c = |n: &str| n != "a" && n != "c";
+        assert_eq!(
+            next_in_rotation(&order, Some("b"), not_a_or_c),
+            Some("d".into())
+        );
+        assert_eq!(
+            next_in_rotation(&order, Some("d"), not_a_or_c),
+            Some("b".into())
+        );
+        assert_eq!(next_in_rotation(&order, Some("b"), |n| n == "b"), None);
+    }
+
+    #[test]
+    fn rotation_from_outside_the_list_starts_at_the_top() {
+        let order = ["a", "b"].map(str::to_string);
+        assert_eq!(
+            next_in_rotation(&order, Some("x"), |_| true),
+            Some("a".into())
+        );
+        assert_eq!(
+            next_in_rotation(&order, None, |n| n != "a"),
+            Some("b".into())
+        );
+        assert_eq!(next_in_rotation(&[], Some("x"), |_| true), None);
+    }
+}
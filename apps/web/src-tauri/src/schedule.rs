@@ -0,0 +1,252 @@
+//! Scheduled full-vault backups.
+//!
+//! When `backup_schedule` is enabled, the vault (`accounts/`, `archived/`,
+//! `config.json`, `settings.json`, `projects.json`) is zipped to `backups/.vault/<ts>.zip`
+//! once per daily or weekly slot. A run whose content hash matches the last
+//! backup is skipped. Keychain-held credentials stay in the keychain and are
+//! not part of the archive.
+
+use chrono::{DateTime, Datelike, Days, Local, NaiveDate};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{Cursor, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{AppHandle, Runtime};
+
+use crate::accounts::{accounts_dir, write_private};
+use crate::backup::{apply_retention_in, backups_root, hash_file, list_files, new_timestamp};
+use crate::i18n::tr;
+use crate::lifecycle::archived_dir;
+use crate::settings::{load_settings, save_settings, BackupCadence, BackupSchedule};
+use crate::{alerts, codex_root, update_tray};
+
+/// How often the scheduler wakes to see whether a slot is due.
+const SCHEDULE_CHECK: Duration = Duration::from_secs(5 * 60);
+
+/// Vault-level files included next to `accounts/`.
+const ROOT_FILES: [&str; 3] = ["config.json", "settings.json", "projects.json"];
+
+/// The leading dot keeps this out of the account namespace.
+pub fn vault_backups_dir() -> PathBuf {
+    backups_root().join(".vault")
+}
+
+/// Persisted alongside the archives; times are RFC 3339.
+#[derive(serde::Serialize, serde::Deserialize, Default)]
+struct ScheduleState {
+    /// When a backup was last written.
+    last_backup_at: Option<String>,
+    /// When a scheduled run last happened, written or skipped.
+    last_run_at: Option<String>,
+    content_hash: Option<String>,
+}
+
+fn parse_time(value: Option<&str>) -> Option<DateTime<Local>> {
+    DateTime::parse_from_rfc3339(value?)
+        .ok()
+        .map(|t| t.with_timezone(&Local))
+}
+
+fn state_path() -> PathBuf {
+    vault_backups_dir().join("state.json")
+}
+
+fn load_schedule_state() -> ScheduleState {
+    fs::read_to_string(state_path())
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save_schedule_state(state: &ScheduleState) -> Result<(), String> {
+    let body = serde_json::to_vec_pretty(state)
+        .map_err(|e| format!("Failed to serialize backup state: {}", e))?;
+    write_private(&state_path(), &body)
+}
+
+/// Every file that goes into a vault backup, keyed by its archive path.
+fn vault_files() -> Result<BTreeMap<String, PathBuf>, String> {
+    let mut files: BTreeMap<String, PathBuf> = list_files(&accounts_dir())?
+        .into_iter()
+        .map(|(rel, path)| (format!("accounts/{}", rel), path))
+        .collect();
+    if archived_dir().is_dir() {
+        files.extend(
+            list_files(&archived_dir())?
+                .into_iter()
+                .map(|(rel, path)| (format!("archived/{}", rel), path)),
+        );
+    }
+    for name in ROOT_FILES {
+        let path = codex_root().join(name);
+        if path.is_file() {
+            files.insert(name.to_string(), path);
+        }
+    }
+    Ok(files)
+}
+
+fn content_hash(files: &BTreeMap<String, PathBuf>) -> Result<String, String> {
+    let mut hasher = Sha256::new();
+    for (rel, path) in files {
+        hasher.update(rel.as_bytes());
+        hasher.update([0]);
+        hasher.update(hash_file(path)?.as_bytes());
+        hasher.update([b'\n']);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Zips the vault and returns the archive path, or `None` when `force` is
+/// off and nothing changed since the last backup.
+pub fn backup_vault(force: bool) -> Result<Option<String>, String> {
+    let files = vault_files()?;
+    let hash = content_hash(&files)?;
+    let mut state = load_schedule_state();
+    state.last_run_at = Some(Local::now().to_rfc3339());
+    if !force && state.content_hash.as_deref() == Some(hash.as_str()) {
+        save_schedule_state(&state)?;
+        return Ok(None);
+    }
+
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    for (rel, path) in &files {
+        let bytes = fs::read(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.to_string_lossy(), e))?;
+        zip.start_file(rel.as_str(), options)
+            .map_err(|e| format!("Failed to archive {}: {}", rel, e))?;
+        zip.write_all(&bytes)
+            .map_err(|e| format!("Failed to archive {}: {}", rel, e))?;
+    }
+    let archive = zip
+        .finish()
+        .map_err(|e| format!("Failed to finish archive: {}", e))?
+        .into_inner();
+
+    let dest = vault_backups_dir().join(format!("{}.zip", new_timestamp()));
+    write_private(&dest, &archive)?;
+    apply_retention_in(&vault_backups_dir());
+
+    state.last_backup_at = state.last_run_at.clone();
+    state.content_hash = Some(hash);
+    save_schedule_state(&state)?;
+    Ok(Some(dest.to_string_lossy().into_owned()))
+}
+
+fn is_slot_day(schedule: &BackupSchedule, date: NaiveDate) -> bool {
+    schedule.cadence == BackupCadence::Daily
+        || date.weekday().num_days_from_monday() == schedule.weekday
+}
+
+fn slot_on(schedule: &BackupSchedule, date: NaiveDate) -> Option<DateTime<Local>> {
+    date.and_hms_opt(schedule.hour, 0, 0)?
+        .and_local_timezone(Local)
+        .earliest()
+}
+
+/// The most recent slot at or before `now`.
+fn previous_slot(schedule: &BackupSchedule, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    let today = now.date_naive();
+    (0..8)
+        .filter_map(|n| today.checked_sub_days(Days::new(n)))
+        .filter(|d| is_slot_day(schedule, *d))
+        .filter_map(|d| slot_on(schedule, d))
+        .find(|slot| *slot <= now)
+}
+
+/// The first slot after `now`.
+fn next_slot(schedule: &BackupSchedule, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    let today = now.date_naive();
+    (0..8)
+        .filter_map(|n| today.checked_add_days(Days::new(n)))
+        .filter(|d| is_slot_day(schedule, *d))
+        .filter_map(|d| slot_on(schedule, d))
+        .find(|slot| *slot > now)
+}
+
+/// Runs the scheduled backup if its slot has passed since the last run.
+fn run_if_due<R: Runtime>(app: &AppHandle<R>) {
+    let schedule = load_settings().backup_schedule;
+    if !schedule.enabled {
+        return;
+    }
+    let now = Local::now();
+    let last_run = parse_time(load_schedule_state().last_run_at.as_deref());
+    let due =
+        previous_slot(&schedule, now).is_some_and(|slot| last_run.map_or(true, |run| run < slot));
+    if !due {
+        return;
+    }
+
+    let result = backup_vault(false);
+    if let Err(e) = &result {
+        log::error!("Scheduled backup failed: {}", e);
+    }
+    alerts::vault_backup(app, &result);
+    let app_for_closure = app.clone();
+    let _ = app.run_on_main_thread(move || {
+        let _ = update_tray(&app_for_closure);
+    });
+}
+
+/// Checks for a due backup every `SCHEDULE_CHECK` for the life of the app.
+pub fn start_backup_scheduler<R: Runtime>(app: AppHandle<R>) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            run_if_due(&app);
+            tokio::time::sleep(SCHEDULE_CHECK).await;
+        }
+    });
+}
+
+/// "Last backup" text for the tray.
+pub fn last_backup_label() -> String {
+    match parse_time(load_schedule_state().last_backup_at.as_deref()) {
+        Some(at) => tr!("tray.last_backup", time = at.format("%Y-%m-%d %H:%M")),
+        None => tr!("tray.last_backup_never"),
+    }
+}
+
+#[derive(serde::Serialize)]
+pub struct BackupScheduleStatus {
+    schedule: BackupSchedule,
+    last_backup_at: Option<String>,
+    next_run_at: Option<String>,
+}
+
+#[tauri::command]
+pub fn get_backup_schedule() -> BackupScheduleStatus {
+    let schedule = load_settings().backup_schedule;
+    let next_run_at = if schedule.enabled {
+        next_slot(&schedule, Local::now()).map(|t| t.to_rfc3339())
+    } else {
+        None
+    };
+    BackupScheduleStatus {
+        last_backup_at: load_schedule_state().last_backup_at,
+        next_run_at,
+        schedule,
+    }
+}
+
+#[tauri::command]
+pub fn set_backup_schedule(app: AppHandle, schedule: BackupSchedule) -> Result<(), String> {
+    schedule.validate()?;
+    let mut settings = load_settings();
+    settings.backup_schedule = schedule;
+    save_settings(&settings)?;
+    let _ = update_tray(&app);
+    Ok(())
+}
+
+/// Backs up the whole vault right away, even if nothing changed.
+#[tauri::command]
+pub fn backup_vault_now(app: AppHandle) -> Result<String, String> {
+    let path = backup_vault(true)?.unwrap_or_default();
+    let _ = update_tray(&app);
+    Ok(path)
+}
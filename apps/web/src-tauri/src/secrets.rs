@@ -0,0 +1,188 @@
+//! Optional OS keychain storage for plaintext account credentials.
+//!
+//! When `keychain_secrets` is on, each account's `auth.json` is moved into the
+//! platform store (macOS Keychain, Windows Credential Manager, libsecret) and
+//! replaced by an empty `auth.keychain` marker. The Python CLI cannot read
+//! the keychain, so this stays opt-in and `export_plaintext` reverses it.
+//! Encrypted (`auth.enc`) accounts are left alone.
+
+use std::fs;
+use tauri::{AppHandle, Runtime};
+
+use crate::accounts::{account_dir, validate_account_name, write_private};
+use crate::i18n::tr;
+use crate::settings::{load_settings, save_settings};
+use crate::{current_state, with_watcher_paused};
+
+const SERVICE: &str = "codex-account-switch";
+
+/// Present in an account dir whose credentials live in the keychain.
+pub const KEYCHAIN_MARKER: &str = "auth.keychain";
+
+fn entry(name: &str) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(SERVICE, name).map_err(|e| tr!("error.keychain_unavailable", error = e))
+}
+
+/// An account's plaintext `auth.json` contents, wherever they are stored.
+/// `Ok(None)` means the account has no plaintext credentials (e.g. `auth.enc`).
+pub fn read_plaintext(name: &str) -> Result<Option<String>, String> {
+    let dir = account_dir(name);
+    let plain = dir.join("auth.json");
+    if plain.exists() {
+        return fs::read_to_string(&plain)
+            .map(Some)
+            .map_err(|e| tr!("error.auth_json_unreadable", error = e));
+    }
+    if dir.join(KEYCHAIN_MARKER).exists() {
+        return entry(name)?
+            .get_password()
+            .map(Some)
+            .map_err(|e| tr!("error.keychain_unreadable", name = name, error = e));
+    }
+    Ok(None)
+}
+
+/// Re-keys a keychain entry after its account dir was renamed to `new`.
+pub fn rename_secret(old: &str, new: &str) -> Result<(), String> {
+    if !account_dir(new).join(KEYCHAIN_MARKER).exists() {
+        return Ok(());
+    }
+    move_secret(old, new)
+}
+
+/// Moves the keychain entry stored under `old` to `new`. Archived and
+/// deleted accounts are kept under their own keys (see `lifecycle.rs`), so a
+/// new account reusing the name can't overwrite them.
+pub fn move_secret(old: &str, new: &str) -> Result<(), String> {
+    let old_entry = entry(old)?;
+    let content = old_entry
+        .get_password()
+        .map_err(|e| tr!("error.keychain_unreadable", name = old, error = e))?;
+    copy_secret_to(&content, new)?;
+    let _ = old_entry.delete_credential();
+    Ok(())
+}
+
+/// Stores a copy of `name`'s keychain entry under `dest`.
+pub fn copy_secret(name: &str, dest: &str) -> Result<(), String> {
+    let content = entry(name)?
+        .get_password()
+        .map_err(|e| tr!("error.keychain_unreadable", name = name, error = e))?;
+    copy_secret_to(&content, dest)
+}
+
+fn copy_secret_to(content: &str, dest: &str) -> Result<(), String> {
+    entry(dest)?
+        .set_password(content)
+        .map_err(|e| tr!("error.keychain_store_failed", error = e))
+}
+
+/// Drops the keychain entry of a removed account, if it had one.
+pub fn forget(name: &str) {
+    if let Ok(entry) = entry(name) {
+        let _ = entry.delete_credential();
+    }
+}
+
+/// Moves one account's `auth.json` into the keychain. The file is only
+/// removed after the stored secret reads back identically.
+pub fn migrate_account(name: &str) -> Result<bool, String> {
+    let dir = account_dir(name);
+    let plain = dir.join("auth.json");
+    if !plain.exists() {
+        return Ok(false);
+    }
+    let content =
+        fs::read_to_string(&plain).map_err(|e| tr!("error.auth_json_unreadable", error = e))?;
+
+    let entry = entry(name)?;
+    entry
+        .set_password(&content)
+        .map_err(|e| tr!("error.keychain_store_failed", error = e))?;
+    if entry.get_password().ok().as_deref() != Some(content.as_str()) {
+        return Err(tr!("error.keychain_verify_failed"));
+    }
+
+    write_private(&dir.join(KEYCHAIN_MARKER), b"")?;
+    fs::remove_file(&plain).map_err(|e| format!("Failed to remove auth.json: {}", e))?;
+    Ok(true)
+}
+
+/// Moves a keychain account back to a plaintext `auth.json`.
+fn export_account(name: &str) -> Result<bool, String> {
+    let dir = account_dir(name);
+    let marker = dir.join(KEYCHAIN_MARKER);
+    if !marker.exists() {
+        return Ok(false);
+    }
+    let entry = entry(name)?;
+    let content = entry
+        .get_password()
+        .map_err(|e| tr!("error.keychain_unreadable", name = name, error = e))?;
+
+    write_private(&dir.join("auth.json"), content.as_bytes())?;
+    fs::remove_file(&marker).map_err(|e| format!("Failed to remove marker: {}", e))?;
+    if let Err(e) = entry.delete_credential() {
+        log::warn!("Failed to delete keychain entry for '{}': {}", name, e);
+    }
+    Ok(true)
+}
+
+#[derive(serde::Serialize, Default)]
+pub struct SecretsReport {
+    moved: Vec<String>,
+    failed: Vec<(String, String)>,
+}
+
+fn for_each_account(
+    accounts: &[String],
+    f: impl Fn(&str) -> Result<bool, String>,
+) -> SecretsReport {
+    let mut report = SecretsReport::default();
+    for name in accounts {
+        if validate_account_name(name).is_err() {
+            continue;
+        }
+        match f(name) {
+            Ok(true) => report.moved.push(name.clone()),
+            Ok(false) => {}
+            Err(e) => report.failed.push((name.clone(), e)),
+        }
+    }
+    report
+}
+
+/// Migrates plaintext accounts at launch when keychain storage is enabled.
+pub fn migrate_on_launch<R: Runtime>(app: &AppHandle<R>) {
+    if !load_settings().keychain_secrets {
+        return;
+    }
+    let report = for_each_account(&current_state(app).accounts, migrate_account);
+    for (name, e) in &report.failed {
+        log::warn!("Failed to move '{}' to the keychain: {}", name, e);
+    }
+}
+
+/// Turns on keychain storage and moves every plaintext account into it.
+#[tauri::command]
+pub fn migrate_secrets(app: AppHandle) -> Result<SecretsReport, String> {
+    let mut settings = load_settings();
+    settings.keychain_secrets = true;
+    save_settings(&settings)?;
+    let accounts = current_state(&app).accounts;
+    Ok(with_watcher_paused(&app, || {
+        for_each_account(&accounts, migrate_account)
+    }))
+}
+
+/// Turns keychain storage off and writes every account back to `auth.json`.
+#[tauri::command]
+pub fn export_plaintext(app: AppHandle) -> Result<SecretsReport, String> {
+    let mut settings = load_settings();
+    settings.keychain_secrets = false;
+    save_settings(&settings)?;
+    let accounts = current_state(&app).accounts;
+    Ok(with_watcher_paused(&app, || {
+        for_each_account(&accounts, export_account)
+    }))
+}
@@ -0,0 +1,215 @@
+//! Keeps switches from rewriting `~/.codex/auth.json` under a running Codex
+//! session (see `process::detect_codex`).
+//!
+//! Every switch goes through `switch`, which refuses it, queues it until the
+//! session ends, or applies it, depending on its `SessionPolicy`. Only one
+//! switch is queued at a time; queueing another replaces it. Overrides, which
+//! can't be queued, check `ensure_idle` instead.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use crate::accounts::{ensure_exists, switch_to, SwitchError, SwitchStage};
+use crate::i18n::tr;
+use crate::process::{detect_codex, CodexRunning};
+use crate::settings::load_settings;
+use crate::{alerts, notify, update_tray};
+
+/// How often a queued switch checks whether the session ended.
+const SESSION_POLL: Duration = Duration::from_secs(5);
+
+#[derive(Default)]
+pub struct PendingSwitch {
+    account: Mutex<Option<String>>,
+    /// A waiter thread is running.
+    waiting: AtomicBool,
+}
+
+#[derive(serde::Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SwitchOutcome {
+    Switched,
+    /// Waiting for the running session to end.
+    Queued,
+}
+
+/// What a switch does while a Codex session is running.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SessionPolicy {
+    /// Fail with a `session` error and a `codex-running` event.
+    Refuse,
+    /// Wait for the session to end.
+    Queue,
+    /// Switch anyway.
+    Force,
+}
+
+/// What becomes of a switch under a policy, given the running session.
+pub enum Gate {
+    Apply,
+    Queue,
+    Refuse(CodexRunning),
+}
+
+pub fn gate(policy: SessionPolicy, session: Option<CodexRunning>) -> Gate {
+    match (policy, session) {
+        (SessionPolicy::Force, _) | (_, None) => Gate::Apply,
+        (SessionPolicy::Queue, Some(_)) => Gate::Queue,
+        (SessionPolicy::Refuse, Some(status)) => Gate::Refuse(status),
+    }
+}
+
+/// The running session that should hold back a switch, if any. Always `None`
+/// when `warn_if_codex_running` is off.
+pub fn active_session() -> Option<CodexRunning> {
+    if !load_settings().warn_if_codex_running {
+        return None;
+    }
+    Some(detect_codex()).filter(|status| status.running)
+}
+
+fn take_pending<R: Runtime>(app: &AppHandle<R>) -> Option<String> {
+    app.try_state::<PendingSwitch>()?
+        .account
+        .lock()
+        .ok()?
+        .take()
+}
+
+/// Applies the queued switch once no session is detected. Returns when the
+/// queue is empty.
+fn wait_for_session_end<R: Runtime>(app: AppHandle<R>) {
+    std::thread::spawn(move || {
+        loop {
+            std::thread::sleep(SESSION_POLL);
+            let Some(pending) = app.try_state::<PendingSwitch>() else {
+                return;
+            };
+            let queued = pending.account.lock().ok().and_then(|a| a.clone());
+            if queued.is_none() {
+                break;
+            }
+            if active_session().is_some() {
+                continue;
+            }
+            let Some(account) = take_pending(&app) else {
+                break;
+            };
+
+            let result = switch_to(&account);
+            alerts::auto_backup(&app, &result);
+            match result {
+                Ok(_) => {
+                    notify(
+                        &app,
+                        &tr!("notify.switched.title"),
+                        &tr!("notify.switched.now_using", name = account),
+                    );
+                    let app_for_closure = app.clone();
+                    let _ = app.run_on_main_thread(move || {
+                        let _ = update_tray(&app_for_closure);
+                    });
+                }
+                Err(e) => {
+                    log::error!("Queued switch to '{}' failed: {}", account, e);
+                    let _ = app.emit("switch-failed", e);
+                }
+            }
+            break;
+        }
+        let Some(pending) = app.try_state::<PendingSwitch>() else {
+            return;
+        };
+        pending.waiting.store(false, Ordering::SeqCst);
+        // A switch queued while this one was being applied needs a new waiter.
+        let requeued = pending.account.lock().is_ok_and(|a| a.is_some());
+        if requeued && !pending.waiting.swap(true, Ordering::SeqCst) {
+            wait_for_session_end(app.clone());
+        }
+    });
+}
+
+/// Switches to `account`, unless a running Codex session holds it back under
+/// `policy`, and reports a failed pre-switch backup. Refreshing the tray is
+/// left to the caller.
+pub fn switch<R: Runtime>(
+    app: &AppHandle<R>,
+    account: &str,
+    policy: SessionPolicy,
+) -> Result<SwitchOutcome, SwitchError> {
+    let session = match policy {
+        SessionPolicy::Force => None,
+        _ => active_session(),
+    };
+    match gate(policy, session) {
+        Gate::Apply => {}
+        Gate::Queue => {
+            ensure_exists(account).map_err(|e| SwitchError::new(SwitchStage::Validate, e))?;
+            log::info!("Codex is running; queued switch to '{}'", account);
+            queue_switch(app, account);
+            return Ok(SwitchOutcome::Queued);
+        }
+        Gate::Refuse(status) => {
+            ensure_exists(account).map_err(|e| SwitchError::new(SwitchStage::Validate, e))?;
+            return Err(SwitchError::new(SwitchStage::Session, refuse(app, status)));
+        }
+    }
+
+    let result = switch_to(account);
+    alerts::auto_backup(app, &result);
+    result.map(|_| SwitchOutcome::Switched)
+}
+
+/// For swaps of the live credentials that can't wait for the session to end,
+/// like pushing or popping an override: fails with the `session` error and a
+/// `codex-running` event while Codex is running, unless `force`.
+pub fn ensure_idle<R: Runtime>(app: &AppHandle<R>, force: bool) -> Result<(), String> {
+    let (policy, session) = if force {
+        (SessionPolicy::Force, None)
+    } else {
+        (SessionPolicy::Refuse, active_session())
+    };
+    match gate(policy, session) {
+        Gate::Refuse(status) => Err(refuse(app, status)),
+        Gate::Apply | Gate::Queue => Ok(()),
+    }
+}
+
+/// The `session` error for `status`, announced with a `codex-running` event.
+fn refuse<R: Runtime>(app: &AppHandle<R>, status: CodexRunning) -> String {
+    let message = tr!("error.codex_running", reasons = status.reasons.join(", "));
+    let _ = app.emit("codex-running", status);
+    message
+}
+
+/// Queues a switch to `account` for when the current session ends.
+pub fn queue_switch<R: Runtime>(app: &AppHandle<R>, account: &str) {
+    let Some(pending) = app.try_state::<PendingSwitch>() else {
+        return;
+    };
+    if let Ok(mut queued) = pending.account.lock() {
+        *queued = Some(account.to_string());
+    }
+    let _ = app.emit("switch-queued", account);
+    if !pending.waiting.swap(true, Ordering::SeqCst) {
+        wait_for_session_end(app.clone());
+    }
+}
+
+/// The account a queued switch is waiting to apply.
+#[tauri::command]
+pub fn pending_switch(app: AppHandle) -> Option<String> {
+    app.try_state::<PendingSwitch>()?
+        .account
+        .lock()
+        .ok()?
+        .clone()
+}
+
+/// Drops the queued switch; returns the account it was for.
+#[tauri::command]
+pub fn cancel_pending_switch(app: AppHandle) -> Option<String> {
+    take_pending(&app)
+}
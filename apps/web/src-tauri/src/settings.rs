@@ -0,0 +1,896 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use crate::accounts::{account_dir, validate_account_name, write_private};
+use crate::i18n::tr;
+use crate::paths::RootOrigin;
+use crate::{autostart, codex_root, i18n, rebind_hotkey, rotation, sync, update_tray};
+
+/// Schema version written to `settings.json`. Bump it and add a step to
+/// `migrate` when a field is renamed or reshaped; new fields only need a default.
+pub const SETTINGS_VERSION: u32 = 1;
+
+/// Timed rotation across accounts (see `rotation.rs`).
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct RotationSettings {
+    /// Restart rotation automatically on launch.
+    pub enabled: bool,
+    pub interval_minutes: u64,
+    /// Rotation order; empty means every account in tray order.
+    pub accounts: Vec<String>,
+}
+
+impl Default for RotationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_minutes: 60,
+            accounts: Vec::new(),
+        }
+    }
+}
+
+/// Background usage polling (see `polling.rs`).
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct PollingSettings {
+    pub enabled: bool,
+    pub interval_minutes: u64,
+    /// Up to this many seconds are added to each wait to avoid rate limits.
+    pub jitter_seconds: u64,
+    /// Limits endpoint; the CLI's `CodexAPI` uses the same one.
+    pub endpoint: String,
+}
+
+impl Default for PollingSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_minutes: 15,
+            jitter_seconds: 60,
+            endpoint: "https://api.codex.io/api/user/limits".to_string(),
+        }
+    }
+}
+
+/// Mirroring into a cloud-synced folder (see `sync.rs`).
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct SyncSettings {
+    pub enabled: bool,
+    pub dir: Option<String>,
+}
+
+/// OAuth device-code login (see `oauth.rs`).
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct LoginSettings {
+    pub client_id: String,
+    pub device_authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub scope: String,
+}
+
+impl Default for LoginSettings {
+    fn default() -> Self {
+        Self {
+            client_id: "codex-account-switch".to_string(),
+            device_authorization_endpoint: "https://auth.codex.io/oauth/device/code".to_string(),
+            token_endpoint: "https://auth.codex.io/oauth/token".to_string(),
+            scope: "openid profile email offline_access".to_string(),
+        }
+    }
+}
+
+/// Which native notifications fire (see `alerts.rs`).
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct NotificationSettings {
+    /// Percentages of a 5h or weekly limit that notify when crossed upward.
+    pub thresholds: Vec<f64>,
+    pub usage_thresholds: bool,
+    /// The active account's 5h or weekly window started over.
+    pub window_resets: bool,
+    /// Any account's window reached its reported reset time (see `resets.rs`).
+    pub reset_times: bool,
+    /// An automatic backup (before a switch, or scheduled) succeeded.
+    pub backup_success: bool,
+    /// An automatic backup (before a switch, or scheduled) failed.
+    pub backup_failure: bool,
+}
+
+impl NotificationSettings {
+    /// Checks thresholds lie in (0, 100], then sorts and deduplicates them.
+    fn normalize(&mut self) -> Result<(), String> {
+        if let Some(bad) = self
+            .thresholds
+            .iter()
+            .find(|t| !(t.is_finite() && **t > 0.0 && **t <= 100.0))
+        {
+            return Err(format!(
+                "Notification threshold {} must be between 0 and 100",
+                bad
+            ));
+        }
+        self.thresholds.sort_by(f64::total_cmp);
+        self.thresholds.dedup();
+        Ok(())
+    }
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            thresholds: vec![80.0, 95.0],
+            usage_thresholds: true,
+            window_resets: true,
+            reset_times: false,
+            backup_success: false,
+            backup_failure: true,
+        }
+    }
+}
+
+/// Periodic credential validation (see `health.rs`).
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct HealthCheckSettings {
+    pub interval_minutes: u64,
+    /// Also ask the limits endpoint whether each token is still accepted.
+    pub ping: bool,
+}
+
+impl Default for HealthCheckSettings {
+    fn default() -> Self {
+        Self {
+            interval_minutes: 60,
+            ping: false,
+        }
+    }
+}
+
+/// How many backups to keep per account (see `backup.rs`). Unset limits
+/// don't prune; the newest backup always survives.
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct BackupRetention {
+    pub keep_last: Option<usize>,
+    pub max_age_days: Option<u64>,
+}
+
+impl Default for BackupRetention {
+    fn default() -> Self {
+        Self {
+            keep_last: Some(20),
+            max_age_days: None,
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BackupCadence {
+    Daily,
+    Weekly,
+}
+
+/// Full-vault backups on a schedule (see `schedule.rs`). Times are local.
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct BackupSchedule {
+    pub enabled: bool,
+    pub cadence: BackupCadence,
+    /// Hour of day (0-23) the backup is due.
+    pub hour: u32,
+    /// Day of week for weekly backups, 0 = Monday.
+    pub weekday: u32,
+}
+
+impl BackupSchedule {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.hour > 23 {
+            return Err(format!(
+                "Backup hour {} must be between 0 and 23",
+                self.hour
+            ));
+        }
+        if self.weekday > 6 {
+            return Err(format!(
+                "Backup weekday {} must be between 0 (Monday) and 6",
+                self.weekday
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for BackupSchedule {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            cadence: BackupCadence::Daily,
+            hour: 3,
+            weekday: 0,
+        }
+    }
+}
+
+/// App-owned preferences, persisted to `~/.codex-accounts/settings.json`.
+///
+/// Every field has a default so older or partial files keep loading.
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    /// Schema version the file was written with; 0 for files from before versioning.
+    #[serde(default)]
+    pub version: u32,
+    /// Render the active account first in the tray instead of in sorted position.
+    pub pin_active_to_top: bool,
+    /// Report live Codex logins that aren't managed accounts yet.
+    pub watch_codex_logins: bool,
+    /// Import detected logins as new accounts instead of only reporting them.
+    pub auto_import_logins: bool,
+    /// Hold watcher-triggered tray rebuilds until the menu closes.
+    pub defer_tray_updates_while_open: bool,
+    /// Append ↑/↓/→ usage trend arrows to tray labels.
+    pub show_usage_trends: bool,
+    /// Usage changes (percentage points) smaller than this count as flat.
+    pub trend_dead_band: f64,
+    /// Usage cache entries older than this are marked "(stale)" in the tray.
+    pub stale_after_minutes: u64,
+    /// Tray only: no Dock icon on macOS, and closing the window hides it
+    /// (see `autostart.rs`).
+    pub background_only: bool,
+    /// Language of the tray, notifications and errors: a bundled locale
+    /// code, or `"auto"` to follow the OS (see `i18n.rs`).
+    pub locale: String,
+    /// Hold back switches while a Codex session is running (see `session.rs`).
+    pub warn_if_codex_running: bool,
+    /// Global shortcut that cycles to the next account (see `set_hotkey`).
+    pub cycle_hotkey: String,
+    /// The hotkey passes over accounts at 100% of either limit.
+    pub cycle_skips_exhausted: bool,
+    pub rotation: RotationSettings,
+    pub backup_retention: BackupRetention,
+    pub backup_schedule: BackupSchedule,
+    pub usage_polling: PollingSettings,
+    pub login: LoginSettings,
+    pub sync: SyncSettings,
+    pub health_checks: HealthCheckSettings,
+    /// Keep plaintext credentials in the OS keychain (see `secrets.rs`).
+    pub keychain_secrets: bool,
+    pub notifications: NotificationSettings,
+    /// Global kill switch for background automations (rotation, auto-switch).
+    pub pause_automations: bool,
+    /// Named account groups: group -> member account names.
+    pub groups: BTreeMap<String, Vec<String>>,
+    /// Per-account tray prefix (usually an emoji), keyed by account name.
+    pub labels: BTreeMap<String, String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            version: SETTINGS_VERSION,
+            pin_active_to_top: false,
+            watch_codex_logins: false,
+            auto_import_logins: false,
+            defer_tray_updates_while_open: false,
+            show_usage_trends: false,
+            trend_dead_band: 2.0,
+            stale_after_minutes: 60,
+            background_only: false,
+            locale: i18n::AUTO_LOCALE.to_string(),
+            warn_if_codex_running: true,
+            cycle_hotkey: "CommandOrControl+Shift+C".to_string(),
+            cycle_skips_exhausted: false,
+            rotation: RotationSettings::default(),
+            backup_retention: BackupRetention::default(),
+            backup_schedule: BackupSchedule::default(),
+            usage_polling: PollingSettings::default(),
+            login: LoginSettings::default(),
+            sync: SyncSettings::default(),
+            health_checks: HealthCheckSettings::default(),
+            keychain_secrets: false,
+            notifications: NotificationSettings::default(),
+            pause_automations: false,
+            groups: BTreeMap::new(),
+            labels: BTreeMap::new(),
+        }
+    }
+}
+
+pub fn settings_path() -> PathBuf {
+    codex_root().join("settings.json")
+}
+
+/// Brings a `settings.json` object written by an older version up to
+/// `SETTINGS_VERSION`, one step at a time.
+fn migrate(value: &mut serde_json::Value) {
+    let Some(object) = value.as_object_mut() else {
+        return;
+    };
+    let version = object.get("version").and_then(|v| v.as_u64()).unwrap_or(0);
+    if version > SETTINGS_VERSION as u64 {
+        log::warn!(
+            "settings.json is version {}, newer than {}; unknown keys are ignored",
+            version,
+            SETTINGS_VERSION
+        );
+        return;
+    }
+    // 0 -> 1: unversioned files already have the version 1 layout.
+    object.insert("version".to_string(), SETTINGS_VERSION.into());
+}
+
+/// Reads a `settings.json` body. A value that doesn't fit its field resets
+/// only that field; anything that isn't a JSON object is an error.
+pub fn parse_settings(content: &[u8]) -> Result<Settings, String> {
+    let mut value: serde_json::Value =
+        serde_json::from_slice(content).map_err(|e| e.to_string())?;
+    if !value.is_object() {
+        return Err("not a JSON object".to_string());
+    }
+    migrate(&mut value);
+    if let Ok(settings) = serde_json::from_value(value.clone()) {
+        return Ok(settings);
+    }
+
+    let mut settings = Settings::default();
+    for (key, field) in value.as_object().into_iter().flatten() {
+        let mut merged = serde_json::to_value(&settings).map_err(|e| e.to_string())?;
+        merged[key.as_str()] = field.clone();
+        match serde_json::from_value(merged) {
+            Ok(parsed) => settings = parsed,
+            Err(e) => log::warn!("Ignoring invalid setting '{}': {}", key, e),
+        }
+    }
+    Ok(settings)
+}
+
+/// The settings last read or saved successfully, for when `settings.json` is
+/// caught mid-write by an editor that doesn't save atomically.
+static LAST_GOOD: Mutex<Option<Settings>> = Mutex::new(None);
+
+fn remember(settings: &Settings) {
+    if let Ok(mut last) = LAST_GOOD.lock() {
+        *last = Some(settings.clone());
+    }
+}
+
+/// Missing settings fall back to defaults rather than blocking the tray. A
+/// file that can't be parsed falls back to the last good settings and is left
+/// alone; only `recovery::prepare` moves it aside, at startup.
+pub fn load_settings() -> Settings {
+    let path = settings_path();
+    let Ok(content) = fs::read(&path) else {
+        return Settings::default();
+    };
+    match parse_settings(&content) {
+        Ok(settings) => {
+            remember(&settings);
+            settings
+        }
+        Err(e) => {
+            log::warn!(
+                "{} is unreadable ({}); using the last good settings",
+                path.to_string_lossy(),
+                e
+            );
+            LAST_GOOD
+                .lock()
+                .ok()
+                .and_then(|last| last.clone())
+                .unwrap_or_default()
+        }
+    }
+}
+
+pub fn save_settings(settings: &Settings) -> Result<(), String> {
+    let settings = Settings {
+        version: SETTINGS_VERSION,
+        ..settings.clone()
+    };
+    let json = serde_json::to_vec_pretty(&settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    write_private(&settings_path(), &json)?;
+    remember(&settings);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_pin_active_to_top(app: AppHandle, enabled: bool) -> Result<(), String> {
+    let mut settings = load_settings();
+    settings.pin_active_to_top = enabled;
+    save_settings(&settings)?;
+    let _ = update_tray(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_login_watch(enabled: bool, auto_import: bool) -> Result<(), String> {
+    let mut settings = load_settings();
+    settings.watch_codex_logins = enabled;
+    settings.auto_import_logins = auto_import;
+    save_settings(&settings)
+}
+
+#[tauri::command]
+pub fn set_defer_tray_updates(enabled: bool) -> Result<(), String> {
+    let mut settings = load_settings();
+    settings.defer_tray_updates_while_open = enabled;
+    save_settings(&settings)
+}
+
+#[tauri::command]
+pub fn set_usage_trends(
+    app: AppHandle,
+    enabled: bool,
+    dead_band: Option<f64>,
+) -> Result<(), String> {
+    let mut settings = load_settings();
+    settings.show_usage_trends = enabled;
+    if let Some(dead_band) = dead_band {
+        if !dead_band.is_finite() || dead_band < 0.0 {
+            return Err("Trend dead-band must be a non-negative number".to_string());
+        }
+        settings.trend_dead_band = dead_band;
+    }
+    save_settings(&settings)?;
+    let _ = update_tray(&app);
+    Ok(())
+}
+
+/// Merges `patch` into `base`: objects key by key, everything else replaced.
+fn merge(base: &mut serde_json::Value, patch: serde_json::Value) {
+    match (base, patch) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(patch)) => {
+            for (key, value) in patch {
+                merge(base.entry(key).or_insert(serde_json::Value::Null), value);
+            }
+        }
+        (base, patch) => *base = patch,
+    }
+}
+
+/// Rejects values the rest of the app can't work with.
+fn validate(settings: &mut Settings) -> Result<(), String> {
+    if !settings.trend_dead_band.is_finite() || settings.trend_dead_band < 0.0 {
+        return Err("Trend dead-band must be a non-negative number".to_string());
+    }
+    for (what, minutes) in [
+        ("Rotation interval", settings.rotation.interval_minutes),
+        ("Polling interval", settings.usage_polling.interval_minutes),
+        (
+            "Health check interval",
+            settings.health_checks.interval_minutes,
+        ),
+    ] {
+        if minutes == 0 {
+            return Err(format!("{} must be at least one minute", what));
+        }
+    }
+    let known = i18n::available()
+        .iter()
+        .any(|code| *code == settings.locale);
+    if settings.locale != i18n::AUTO_LOCALE && !known {
+        return Err(format!(
+            "Unknown locale '{}'; use \"{}\" or one of: {}",
+            settings.locale,
+            i18n::AUTO_LOCALE,
+            i18n::available().join(", ")
+        ));
+    }
+    settings.backup_schedule.validate()?;
+    settings.notifications.normalize()?;
+    for name in settings
+        .groups
+        .values()
+        .chain(std::iter::once(&settings.rotation.accounts))
+        .flatten()
+    {
+        validate_account_name(name)?;
+    }
+    Ok(())
+}
+
+/// The settings the running app last acted on, so `apply` only reacts to
+/// what actually changed.
+pub struct AppliedSettings(Mutex<Settings>);
+
+impl Default for AppliedSettings {
+    fn default() -> Self {
+        Self(Mutex::new(load_settings()))
+    }
+}
+
+/// Puts `settings` into effect without a restart: rebinds the hotkey, starts
+/// or stops rotation, rebuilds the tray and emits `settings-changed`. Pollers
+/// and checkers read settings on every cycle and need nothing here. Does
+/// nothing when `settings` is what was last applied.
+pub fn apply<R: Runtime>(app: &AppHandle<R>, settings: Settings) {
+    let Some(applied) = app.try_state::<AppliedSettings>() else {
+        return;
+    };
+    let Ok(mut applied) = applied.0.lock() else {
+        return;
+    };
+    if *applied == settings {
+        return;
+    }
+    let previous = std::mem::replace(&mut *applied, settings.clone());
+    drop(applied);
+
+    if previous.cycle_hotkey != settings.cycle_hotkey {
+        if let Err(e) = rebind_hotkey(app, &previous.cycle_hotkey, &settings.cycle_hotkey) {
+            log::warn!("{}", e);
+        }
+    }
+    if previous.rotation.enabled != settings.rotation.enabled {
+        rotation::set_running(app, settings.rotation.enabled);
+    }
+    if previous.background_only != settings.background_only {
+        autostart::apply_activation_policy(app, settings.background_only);
+    }
+    if previous.sync != settings.sync {
+        sync::restart(app);
+    }
+    if previous.locale != settings.locale {
+        i18n::set_locale(&settings.locale);
+    }
+    let _ = update_tray(app);
+    let _ = app.emit("settings-changed", &settings);
+}
+
+#[tauri::command]
+pub fn get_settings() -> Settings {
+    load_settings()
+}
+
+/// Deep-merges `patch` (any subset of the settings object) into the current
+/// settings, validates and saves the result, and applies it right away.
+#[tauri::command]
+pub fn update_settings(app: AppHandle, patch: serde_json::Value) -> Result<Settings, String> {
+    if !patch.is_object() {
+        return Err("Settings update must be an object".to_string());
+    }
+    let current = load_settings();
+    let mut value = serde_json::to_value(&current)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    merge(&mut value, patch);
+    let mut settings: Settings =
+        serde_json::from_value(value).map_err(|e| format!("Invalid settings: {}", e))?;
+    validate(&mut settings)?;
+    if settings.cycle_hotkey != current.cycle_hotkey {
+        rebind_hotkey(&app, &current.cycle_hotkey, &settings.cycle_hotkey)?;
+    }
+    save_settings(&settings)?;
+    apply(&app, settings.clone());
+    Ok(settings)
+}
+
+/// Writes the current settings to `dest` (defaults if none were ever saved).
+#[tauri::command]
+pub fn export_settings(dest: String) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(&load_settings())
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    fs::write(&dest, json).map_err(|e| format!("Failed to write '{}': {}", dest, e))
+}
+
+/// Replaces settings with the contents of `src`.
+///
+/// The file must be a JSON object that deserializes into `Settings` and
+/// passes the same checks as `update_settings`; the current `settings.json`
+/// is copied to `backups/settings/` first. The imported settings take effect
+/// right away.
+#[tauri::command]
+pub fn import_settings(app: AppHandle, src: String) -> Result<(), String> {
+    let content =
+        fs::read_to_string(&src).map_err(|e| format!("Failed to read '{}': {}", src, e))?;
+    let mut value: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|e| format!("'{}' is not valid JSON: {}", src, e))?;
+    let Some(object) = value.as_object() else {
+        return Err(format!("'{}' does not contain a settings object", src));
+    };
+    // Every field has a default, so an unrelated object would otherwise
+    // import as a full reset.
+    let known = serde_json::to_value(Settings::default())
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    if !object.keys().any(|key| known.get(key).is_some()) {
+        return Err(format!("'{}' has no settings in it", src));
+    }
+    migrate(&mut value);
+    let mut imported: Settings = serde_json::from_value(value)
+        .map_err(|e| format!("'{}' is not a valid settings file: {}", src, e))?;
+    validate(&mut imported)?;
+
+    let current = settings_path();
+    if current.exists() {
+        let backup_dir = crate::backup::backups_root().join("settings");
+        fs::create_dir_all(&backup_dir)
+            .map_err(|e| format!("Failed to create settings backup dir: {}", e))?;
+        let backup = backup_dir.join(format!("settings-{}.json", crate::backup::new_timestamp()));
+        fs::copy(&current, &backup)
+            .map_err(|e| format!("Failed to back up current settings: {}", e))?;
+    }
+
+    let previous = load_settings();
+    if imported.cycle_hotkey != previous.cycle_hotkey {
+        rebind_hotkey(&app, &previous.cycle_hotkey, &imported.cycle_hotkey)?;
+    }
+    save_settings(&imported)?;
+    apply(&app, imported);
+    let _ = app.emit("settings-imported", ());
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_warn_if_codex_running(enabled: bool) -> Result<(), String> {
+    let mut settings = load_settings();
+    settings.warn_if_codex_running = enabled;
+    save_settings(&settings)
+}
+
+/// Replaces the notification preferences. Thresholds must lie in (0, 100]
+/// and are stored sorted without duplicates.
+#[tauri::command]
+pub fn set_notifications(mut notifications: NotificationSettings) -> Result<(), String> {
+    notifications.normalize()?;
+    let mut settings = load_settings();
+    settings.notifications = notifications;
+    save_settings(&settings)
+}
+
+#[tauri::command]
+pub fn set_pause_automations(paused: bool) -> Result<(), String> {
+    let mut settings = load_settings();
+    settings.pause_automations = paused;
+    save_settings(&settings)
+}
+
+/// Sets or (with an empty prefix) clears `name`'s label; true if it changed.
+fn apply_label(settings: &mut Settings, name: &str, prefix: &str) -> bool {
+    if prefix.is_empty() {
+        settings.labels.remove(name).is_some()
+    } else {
+        settings.labels.insert(name.to_string(), prefix.to_string()) != Some(prefix.to_string())
+    }
+}
+
+/// Labels each of `members` that `exists`; returns how many labels changed.
+fn label_members(
+    settings: &mut Settings,
+    members: &[String],
+    prefix: &str,
+    exists: impl Fn(&str) -> bool,
+) -> usize {
+    members
+        .iter()
+        .filter(|name| exists(name))
+        .filter(|name| apply_label(settings, name, prefix))
+        .count()
+}
+
+/// Defines (or, with an empty list, deletes) an account group.
+#[tauri::command]
+pub fn set_account_group(group: String, accounts: Vec<String>) -> Result<(), String> {
+    if group.trim().is_empty() {
+        return Err(tr!("error.group_name_empty"));
+    }
+    for name in &accounts {
+        validate_account_name(name)?;
+    }
+    let mut settings = load_settings();
+    if accounts.is_empty() {
+        settings.groups.remove(&group);
+    } else {
+        settings.groups.insert(group, accounts);
+    }
+    save_settings(&settings)
+}
+
+/// Points every per-account setting at `new` instead of `old`.
+pub fn rename_in_settings(settings: &mut Settings, old: &str, new: &str) {
+    if let Some(label) = settings.labels.remove(old) {
+        settings.labels.insert(new.to_string(), label);
+    }
+    let members = settings
+        .groups
+        .values_mut()
+        .chain(std::iter::once(&mut settings.rotation.accounts));
+    for name in members.flatten().filter(|n| n.as_str() == old) {
+        *name = new.to_string();
+    }
+}
+
+/// Sets the tray prefix for one account; an empty prefix clears it.
+#[tauri::command]
+pub fn set_account_label(app: AppHandle, name: String, prefix: String) -> Result<(), String> {
+    validate_account_name(&name)?;
+    let mut settings = load_settings();
+    apply_label(&mut settings, &name, &prefix);
+    save_settings(&settings)?;
+    let _ = update_tray(&app);
+    Ok(())
+}
+
+/// Applies one tray prefix to every member of `group` that still exists and
+/// returns how many labels actually changed. An empty prefix clears the
+/// members' labels.
+#[tauri::command]
+pub fn label_group(app: AppHandle, group: String, prefix: String) -> Result<usize, String> {
+    let mut settings = load_settings();
+    let members = settings
+        .groups
+        .get(&group)
+        .cloned()
+        .ok_or_else(|| tr!("error.group_not_found", group = group))?;
+
+    let changed = label_members(&mut settings, &members, &prefix, |name| {
+        account_dir(name).is_dir()
+    });
+    save_settings(&settings)?;
+    let _ = update_tray(&app);
+    Ok(changed)
+}
+
+/// Where an effective value came from, highest precedence first.
+#[derive(serde::Serialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigSource {
+    Env,
+    /// The vault's own `config.json`, shared with the CLI.
+    Manifest,
+    Settings,
+    Default,
+}
+
+#[derive(serde::Serialize)]
+pub struct ConfigValue {
+    key: String,
+    value: serde_json::Value,
+    source: ConfigSource,
+}
+
+#[derive(serde::Serialize)]
+pub struct EffectiveConfig {
+    values: Vec<ConfigValue>,
+}
+
+fn env_value(var: &str) -> Option<String> {
+    std::env::var(var).ok().filter(|v| !v.is_empty())
+}
+
+fn read_json_object(path: &std::path::Path) -> serde_json::Map<String, serde_json::Value> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok())
+        .and_then(|v| v.as_object().cloned())
+        .unwrap_or_default()
+}
+
+/// Every tunable the app reads, with its final value and the layer that won
+/// (env > manifest > settings > default). Included in the support bundle.
+#[tauri::command]
+pub fn effective_config() -> EffectiveConfig {
+    let mut values = Vec::new();
+
+    // Backups live in the vault, so both follow wherever the vault came from.
+    let root_source = match crate::paths::codex_root_origin() {
+        RootOrigin::Env => ConfigSource::Env,
+        RootOrigin::Home | RootOrigin::ConfigDir | RootOrigin::Default => ConfigSource::Default,
+    };
+    values.push(ConfigValue {
+        key: "accounts_root".to_string(),
+        value: codex_root().to_string_lossy().into(),
+        source: root_source,
+    });
+    values.push(ConfigValue {
+        key: "backups_root".to_string(),
+        value: crate::backup::backups_root().to_string_lossy().into(),
+        source: root_source,
+    });
+    values.push(ConfigValue {
+        key: "live_auth_file".to_string(),
+        value: crate::paths::live_auth_path().to_string_lossy().into(),
+        source: if env_value("CODEX_LEGACY_AUTH_FILE").is_some() {
+            ConfigSource::Env
+        } else {
+            ConfigSource::Default
+        },
+    });
+
+    let manifest = read_json_object(&codex_root().join("config.json"));
+    values.push(ConfigValue {
+        key: "active_account".to_string(),
+        value: manifest
+            .get("active_account")
+            .cloned()
+            .unwrap_or(serde_json::Value::Null),
+        source: if manifest.contains_key("active_account") {
+            ConfigSource::Manifest
+        } else {
+            ConfigSource::Default
+        },
+    });
+
+    for (key, default) in [
+        ("tray_sort", serde_json::Value::from("name")),
+        ("auto_switch", serde_json::Value::from(false)),
+        ("auto_switch_threshold", serde_json::Value::from(100.0)),
+    ] {
+        values.push(ConfigValue {
+            key: key.to_string(),
+            value: manifest.get(key).cloned().unwrap_or(default),
+            source: if manifest.contains_key(key) {
+                ConfigSource::Manifest
+            } else {
+                ConfigSource::Default
+            },
+        });
+    }
+
+    let explicit = read_json_object(&settings_path());
+    if let Ok(serde_json::Value::Object(resolved)) = serde_json::to_value(load_settings()) {
+        for (key, value) in resolved {
+            let source = if explicit.contains_key(&key) {
+                ConfigSource::Settings
+            } else {
+                ConfigSource::Default
+            };
+            values.push(ConfigValue { key, value, source });
+        }
+    }
+
+    EffectiveConfig { values }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bad_field_keeps_the_others() {
+        let settings = parse_settings(
+            br#"{
+                "version": 1,
+                "pin_active_to_top": true,
+                "stale_after_minutes": "soon",
+                "cycle_hotkey": "Alt+K",
+                "labels": { "work": "W" }
+            }"#,
+        )
+        .unwrap();
+        assert!(settings.pin_active_to_top);
+        assert_eq!(settings.cycle_hotkey, "Alt+K");
+        assert_eq!(settings.labels.get("work").map(String::as_str), Some("W"));
+        assert_eq!(
+            settings.stale_after_minutes,
+            Settings::default().stale_after_minutes
+        );
+    }
+
+    #[test]
+    fn unparseable_file_is_an_error() {
+        assert!(parse_settings(b"{ \"pin_active_to_top\": tr").is_err());
+        assert!(parse_settings(b"[]").is_err());
+    }
+
+    #[test]
+    fn group_label_counts_only_changed_accounts() {
+        let mut settings = Settings::default();
+        settings.labels.insert("work".to_string(), "W".to_string());
+        let members = ["work", "home", "gone"].map(str::to_string);
+        let exists = |name: &str| name != "gone";
+
+        assert_eq!(label_members(&mut settings, &members, "W", exists), 1);
+        assert_eq!(label_members(&mut settings, &members, "W", exists), 0);
+        assert!(!settings.labels.contains_key("gone"));
+        assert_eq!(label_members(&mut settings, &members, "", exists), 2);
+        assert!(settings.labels.is_empty());
+    }
+}
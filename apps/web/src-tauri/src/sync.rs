@@ -0,0 +1,432 @@
+//! Two-way mirroring of `accounts/` and `backups/` into a cloud-synced folder
+//! (iCloud Drive, Dropbox, OneDrive, ...).
+//!
+//! Files go to `<dir>/codex-accounts/`. `.sync-state.json` in the vault keeps
+//! each file's hash as of the last sync, so a pass can tell which side
+//! changed. When both did, the newer file wins and the other is kept under
+//! `<dir>/codex-accounts/.conflicts/<timestamp>/`. A file deleted on one side
+//! and edited on the other is kept. Machine-local files (`config.json`,
+//! `settings.json`, the usage cache) are not mirrored, and neither are
+//! keychain-held credentials, only their markers; everything else is copied
+//! as-is, so the folder holds whatever plaintext the vault holds.
+//!
+//! Both sides are watched, with a periodic pass as a fallback for sync
+//! clients that don't produce file events. Progress is emitted as `sync-status`.
+
+use notify::{Config as NotifyConfig, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Emitter, Manager, Runtime, State};
+
+use crate::accounts::write_private;
+use crate::backup::{hash_file, list_files, new_timestamp};
+use crate::settings::{self, load_settings, save_settings};
+use crate::{codex_root, update_tray, with_watcher_paused};
+
+/// Top-level vault directories that are mirrored.
+const SYNC_DIRS: [&str; 2] = ["accounts", "backups"];
+
+/// Folder created inside the configured directory.
+const REMOTE_DIR_NAME: &str = "codex-accounts";
+
+const CONFLICTS_DIR: &str = ".conflicts";
+
+/// Quiet period after file events before a pass runs.
+const SYNC_DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// Fallback pass interval when no events arrive.
+const SYNC_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// How often the watcher thread checks whether it was replaced.
+const SYNC_TICK: Duration = Duration::from_secs(1);
+
+#[derive(serde::Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncState {
+    #[default]
+    Disabled,
+    Idle,
+    Syncing,
+    Error,
+}
+
+#[derive(serde::Serialize, Clone, Default)]
+pub struct SyncReport {
+    /// Copied from the sync folder into the vault (including deletions).
+    pulled: Vec<String>,
+    /// Copied from the vault into the sync folder (including deletions).
+    pushed: Vec<String>,
+    /// Changed on both sides; the older copy went to `.conflicts/`.
+    conflicts: Vec<String>,
+}
+
+#[derive(serde::Serialize, Clone, Default)]
+pub struct SyncStatus {
+    state: SyncState,
+    dir: Option<String>,
+    last_sync_at: Option<String>,
+    last_report: Option<SyncReport>,
+    error: Option<String>,
+}
+
+/// Bumping `generation` stops the running watcher; `pass` serializes passes.
+#[derive(Default)]
+pub struct SyncControl {
+    generation: AtomicU64,
+    pass: Mutex<()>,
+    status: Mutex<SyncStatus>,
+}
+
+fn state_path() -> PathBuf {
+    codex_root().join(".sync-state.json")
+}
+
+/// Hashes as of the last completed pass, by relative path.
+fn load_base() -> BTreeMap<String, String> {
+    fs::read_to_string(state_path())
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save_base(base: &BTreeMap<String, String>) -> Result<(), String> {
+    let body = serde_json::to_vec_pretty(base)
+        .map_err(|e| format!("Failed to serialize sync state: {}", e))?;
+    write_private(&state_path(), &body)
+}
+
+pub fn remote_root(dir: &str) -> PathBuf {
+    Path::new(dir).join(REMOTE_DIR_NAME)
+}
+
+/// In-flight temp files and sign-in staging dirs are never mirrored.
+fn is_transient(rel: &str) -> bool {
+    rel.split('/')
+        .any(|part| part.ends_with(".tmp") || part.starts_with(".login-"))
+}
+
+/// The mirrored files under `root`, by `/`-separated relative path.
+fn scan(root: &Path) -> Result<BTreeMap<String, PathBuf>, String> {
+    let mut files = BTreeMap::new();
+    for dir in SYNC_DIRS {
+        let path = root.join(dir);
+        if !path.is_dir() {
+            continue;
+        }
+        for (rel, file) in list_files(&path)? {
+            let rel = format!("{}/{}", dir, rel);
+            if !is_transient(&rel) {
+                files.insert(rel, file);
+            }
+        }
+    }
+    Ok(files)
+}
+
+fn hashes(files: &BTreeMap<String, PathBuf>) -> Result<BTreeMap<String, String>, String> {
+    files
+        .iter()
+        .map(|(rel, path)| Ok((rel.clone(), hash_file(path)?)))
+        .collect()
+}
+
+fn modified(path: &Path) -> SystemTime {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .unwrap_or(SystemTime::UNIX_EPOCH)
+}
+
+fn copy_file(from: &Path, to: &Path) -> Result<(), String> {
+    let bytes =
+        fs::read(from).map_err(|e| format!("Failed to read {}: {}", from.to_string_lossy(), e))?;
+    write_private(to, &bytes)
+}
+
+/// Removes `path`, then any directories it leaves empty below `root`, so a
+/// deleted account doesn't linger as an empty account directory.
+fn remove_file(root: &Path, path: &Path) -> Result<(), String> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            return Err(format!(
+                "Failed to remove {}: {}",
+                path.to_string_lossy(),
+                e
+            ))
+        }
+        _ => {}
+    }
+    let mut dir = path.parent();
+    while let Some(d) = dir.filter(|d| d.starts_with(root) && *d != root) {
+        if fs::remove_dir(d).is_err() {
+            break;
+        }
+        dir = d.parent();
+    }
+    Ok(())
+}
+
+/// One reconciliation of the vault with `remote`.
+fn sync_dirs(local: &Path, remote: &Path) -> Result<SyncReport, String> {
+    let local_files = scan(local)?;
+    let remote_files = scan(remote)?;
+    let local_hashes = hashes(&local_files)?;
+    let remote_hashes = hashes(&remote_files)?;
+    let mut base = load_base();
+
+    let paths: BTreeSet<String> = local_hashes
+        .keys()
+        .chain(remote_hashes.keys())
+        .chain(base.keys())
+        .cloned()
+        .collect();
+    let conflict_dir = remote.join(CONFLICTS_DIR).join(new_timestamp());
+    let mut report = SyncReport::default();
+
+    for rel in paths {
+        let l = local_hashes.get(&rel);
+        let r = remote_hashes.get(&rel);
+        let b = base.get(&rel);
+        let local_path = local.join(&rel);
+        let remote_path = remote.join(&rel);
+
+        let merged = if l == r {
+            l.cloned()
+        } else if l == b {
+            // Only the sync folder changed.
+            match r {
+                Some(_) => copy_file(&remote_path, &local_path)?,
+                None => remove_file(local, &local_path)?,
+            }
+            report.pulled.push(rel.clone());
+            r.cloned()
+        } else if r == b {
+            // Only the vault changed.
+            match l {
+                Some(_) => copy_file(&local_path, &remote_path)?,
+                None => remove_file(remote, &remote_path)?,
+            }
+            report.pushed.push(rel.clone());
+            l.cloned()
+        } else {
+            match (l, r) {
+                // Edited on one side, deleted on the other: keep the edit.
+                (Some(_), None) => {
+                    copy_file(&local_path, &remote_path)?;
+                    report.pushed.push(rel.clone());
+                    l.cloned()
+                }
+                (None, Some(_)) => {
+                    copy_file(&remote_path, &local_path)?;
+                    report.pulled.push(rel.clone());
+                    r.cloned()
+                }
+                _ => {
+                    let local_wins = modified(&local_path) >= modified(&remote_path);
+                    if local_wins {
+                        copy_file(&remote_path, &conflict_dir.join(&rel))?;
+                        copy_file(&local_path, &remote_path)?;
+                    } else {
+                        copy_file(&local_path, &conflict_dir.join(&rel))?;
+                        copy_file(&remote_path, &local_path)?;
+                    }
+                    report.conflicts.push(rel.clone());
+                    if local_wins {
+                        l.cloned()
+                    } else {
+                        r.cloned()
+                    }
+                }
+            }
+        };
+        match merged {
+            Some(hash) => base.insert(rel, hash),
+            None => base.remove(&rel),
+        };
+    }
+
+    save_base(&base)?;
+    Ok(report)
+}
+
+fn set_status<R: Runtime>(app: &AppHandle<R>, update: impl FnOnce(&mut SyncStatus)) {
+    let Some(sync) = app.try_state::<SyncControl>() else {
+        return;
+    };
+    let status = {
+        let Ok(mut status) = sync.status.lock() else {
+            return;
+        };
+        update(&mut status);
+        status.clone()
+    };
+    let _ = app.emit("sync-status", status);
+}
+
+/// Runs one pass against the configured folder, updating the status.
+fn run_pass<R: Runtime>(app: &AppHandle<R>) -> Result<SyncReport, String> {
+    let settings = load_settings().sync;
+    let dir = settings
+        .dir
+        .filter(|_| settings.enabled)
+        .ok_or("Sync is not configured")?;
+    let sync = app
+        .try_state::<SyncControl>()
+        .ok_or("Sync state unavailable")?;
+    let _pass = sync.pass.lock().map_err(|_| "Sync state poisoned")?;
+
+    set_status(app, |s| {
+        s.state = SyncState::Syncing;
+        s.dir = Some(dir.clone());
+    });
+    let remote = remote_root(&dir);
+    let result = fs::create_dir_all(&remote)
+        .map_err(|e| format!("Failed to create {}: {}", remote.to_string_lossy(), e))
+        .and_then(|()| with_watcher_paused(app, || sync_dirs(&codex_root(), &remote)));
+    match &result {
+        Ok(report) => set_status(app, |s| {
+            s.state = SyncState::Idle;
+            s.last_sync_at = Some(chrono::Local::now().to_rfc3339());
+            s.last_report = Some(report.clone());
+            s.error = None;
+        }),
+        Err(e) => set_status(app, |s| {
+            s.state = SyncState::Error;
+            s.error = Some(e.clone());
+        }),
+    }
+    result
+}
+
+/// Watches the vault and the sync folder and runs a pass after changes
+/// settle, or every `SYNC_INTERVAL`, until `generation` moves on.
+fn spawn_watcher<R: Runtime>(app: AppHandle<R>, dir: String, generation: u64) {
+    std::thread::spawn(move || {
+        let current = |app: &AppHandle<R>| {
+            app.try_state::<SyncControl>()
+                .map(|s| s.generation.load(Ordering::SeqCst))
+                == Some(generation)
+        };
+
+        let (tx, rx) = channel();
+        let mut watcher = match RecommendedWatcher::new(tx, NotifyConfig::default()) {
+            Ok(w) => w,
+            Err(e) => {
+                log::warn!("Failed to create sync watcher: {:?}", e);
+                return;
+            }
+        };
+        let local = codex_root();
+        let mut roots = vec![remote_root(&dir)];
+        roots.extend(SYNC_DIRS.iter().map(|d| local.join(d)));
+        for root in roots {
+            let _ = fs::create_dir_all(&root);
+            if let Err(e) = watcher.watch(&root, RecursiveMode::Recursive) {
+                log::warn!("Failed to watch {}: {:?}", root.to_string_lossy(), e);
+            }
+        }
+
+        if let Err(e) = run_pass(&app) {
+            log::error!("Sync failed: {}", e);
+        }
+        let mut dirty_since: Option<Instant> = None;
+        let mut last_pass = Instant::now();
+        loop {
+            match rx.recv_timeout(SYNC_TICK) {
+                Ok(Ok(event)) => {
+                    let relevant = event
+                        .paths
+                        .iter()
+                        .any(|p| !p.components().any(|c| c.as_os_str() == CONFLICTS_DIR));
+                    if relevant {
+                        dirty_since = Some(Instant::now());
+                    }
+                }
+                Ok(Err(e)) => log::warn!("Sync watch error: {:?}", e),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+            if !current(&app) {
+                return;
+            }
+            let settled = dirty_since.is_some_and(|t| t.elapsed() >= SYNC_DEBOUNCE);
+            if settled || last_pass.elapsed() >= SYNC_INTERVAL {
+                if let Err(e) = run_pass(&app) {
+                    log::error!("Sync failed: {}", e);
+                }
+                // Events caused by the pass itself only lead to a no-op pass.
+                dirty_since = None;
+                last_pass = Instant::now();
+            }
+        }
+    });
+}
+
+/// Starts (or restarts, or stops) the sync watcher to match settings.
+pub fn restart<R: Runtime>(app: &AppHandle<R>) {
+    let Some(sync) = app.try_state::<SyncControl>() else {
+        return;
+    };
+    let generation = sync.generation.fetch_add(1, Ordering::SeqCst) + 1;
+    let settings = load_settings().sync;
+    match settings.dir.filter(|_| settings.enabled) {
+        Some(dir) => {
+            set_status(app, |s| {
+                s.state = SyncState::Idle;
+                s.dir = Some(dir.clone());
+            });
+            spawn_watcher(app.clone(), dir, generation);
+        }
+        None => set_status(app, |s| {
+            s.state = SyncState::Disabled;
+            s.dir = None;
+        }),
+    }
+}
+
+/// Sets the folder to mirror into (a cloud-synced directory) and turns
+/// syncing on or off. The folder must exist and must not overlap the vault.
+#[tauri::command]
+pub fn configure_sync(app: AppHandle, dir: Option<String>, enabled: bool) -> Result<(), String> {
+    if let Some(dir) = &dir {
+        let path = fs::canonicalize(dir).map_err(|e| format!("'{}' is not usable: {}", dir, e))?;
+        if !path.is_dir() {
+            return Err(format!("'{}' is not a directory", dir));
+        }
+        let vault = fs::canonicalize(codex_root()).unwrap_or_else(|_| codex_root());
+        if path.starts_with(&vault) || vault.starts_with(&path) {
+            return Err("The sync folder can't contain or be inside the vault".to_string());
+        }
+    } else if enabled {
+        return Err("Choose a sync folder before enabling sync".to_string());
+    }
+
+    let mut settings = load_settings();
+    if settings.sync.dir != dir {
+        // The recorded hashes describe the old folder.
+        let _ = fs::remove_file(state_path());
+    }
+    settings.sync.dir = dir;
+    settings.sync.enabled = enabled;
+    save_settings(&settings)?;
+    // Restarts the watcher when anything changed.
+    settings::apply(&app, settings);
+    Ok(())
+}
+
+/// Runs a pass right away.
+#[tauri::command]
+pub fn sync_now(app: AppHandle) -> Result<SyncReport, String> {
+    let report = run_pass(&app)?;
+    let _ = update_tray(&app);
+    Ok(report)
+}
+
+#[tauri::command]
+pub fn sync_status(sync: State<'_, SyncControl>) -> SyncStatus {
+    sync.status.lock().map(|s| s.clone()).unwrap_or_default()
+}
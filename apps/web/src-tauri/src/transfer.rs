@@ -0,0 +1,419 @@
+//! Passphrase-encrypted vault archives for moving accounts between machines.
+//!
+//! An archive is `MAGIC || salt || nonce || AES-256-GCM(zip)`, with the key
+//! derived from the passphrase by Argon2id. The zip holds `manifest.json`,
+//! `accounts/<name>/<file>`, `usage/<name>.json` (the account's usage cache
+//! entry) and `history.jsonl` (its usage samples). Keychain-stored
+//! credentials are exported as a plain `auth.json` inside the encryption.
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, OpenOptions};
+use std::io::{Cursor, Write};
+use tauri::AppHandle;
+
+use crate::accounts::{
+    account_dir, accounts_dir, apply_credentials, validate_account_name, write_private,
+};
+use crate::archive::{copy_bounded, escapes_root, MAX_UNCOMPRESSED_BYTES};
+use crate::backup::{list_files, replace_dir};
+use crate::history::{history_path, read_samples, UsageSample};
+use crate::i18n::tr;
+use crate::secrets::{self, read_plaintext, KEYCHAIN_MARKER};
+use crate::settings::load_settings;
+use crate::{codex_root, load_state, with_watcher_paused};
+
+const MAGIC: &[u8; 8] = b"CXVAULT1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const FORMAT_VERSION: u32 = 1;
+const MIN_PASSPHRASE_LEN: usize = 8;
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct VaultManifest {
+    version: u32,
+    created_at: String,
+    accounts: Vec<String>,
+}
+
+/// What to do with an archived account whose name already exists locally.
+#[derive(serde::Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictPolicy {
+    Skip,
+    Overwrite,
+    /// Import under the first free `<name>-2`, `<name>-3`, ...
+    Rename,
+}
+
+#[derive(serde::Serialize, Default)]
+pub struct VaultImport {
+    /// Archived name -> name it was imported as.
+    imported: BTreeMap<String, String>,
+    skipped: Vec<String>,
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Key<Aes256Gcm>, String> {
+    let mut key = Key::<Aes256Gcm>::default();
+    argon2::Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Failed to derive key: {}", e))?;
+    Ok(key)
+}
+
+fn encrypt(passphrase: &str, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = Aes256Gcm::new(&derive_key(passphrase, &salt)?)
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| "Failed to encrypt archive".to_string())?;
+
+    let mut out = Vec::with_capacity(MAGIC.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+fn decrypt(passphrase: &str, data: &[u8]) -> Result<Vec<u8>, String> {
+    let body = data
+        .strip_prefix(MAGIC.as_slice())
+        .ok_or("Not a vault archive")?;
+    if body.len() < SALT_LEN + NONCE_LEN {
+        return Err("Vault archive is truncated".to_string());
+    }
+    let (salt, rest) = body.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    Aes256Gcm::new(&derive_key(passphrase, salt)?)
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Wrong passphrase or damaged archive".to_string())
+}
+
+/// An account's files as they should appear in the archive.
+fn account_files(name: &str) -> Result<BTreeMap<String, Vec<u8>>, String> {
+    let mut files = BTreeMap::new();
+    for (rel, path) in list_files(&account_dir(name))? {
+        if rel == KEYCHAIN_MARKER {
+            continue;
+        }
+        let bytes = fs::read(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.to_string_lossy(), e))?;
+        files.insert(rel, bytes);
+    }
+    if !files.contains_key("auth.json") {
+        if let Some(content) = read_plaintext(name)? {
+            files.insert("auth.json".to_string(), content.into_bytes());
+        }
+    }
+    Ok(files)
+}
+
+fn read_usage_cache() -> serde_json::Map<String, serde_json::Value> {
+    fs::read_to_string(codex_root().join("usage_cache.json"))
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+/// Packs `accounts` (every account when empty) into an encrypted archive at
+/// `dest` and returns the names exported.
+#[tauri::command]
+pub fn export_vault(
+    dest: String,
+    passphrase: String,
+    accounts: Vec<String>,
+) -> Result<Vec<String>, String> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(format!(
+            "Passphrase must be at least {} characters",
+            MIN_PASSPHRASE_LEN
+        ));
+    }
+    let state = load_state();
+    let accounts = if accounts.is_empty() {
+        state.accounts
+    } else {
+        accounts
+    };
+    for name in &accounts {
+        validate_account_name(name)?;
+        if !account_dir(name).is_dir() {
+            return Err(tr!("error.account_not_found", name = name));
+        }
+    }
+
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    let mut add = |path: String, bytes: &[u8]| -> Result<(), String> {
+        zip.start_file(path.as_str(), options)
+            .map_err(|e| format!("Failed to archive {}: {}", path, e))?;
+        zip.write_all(bytes)
+            .map_err(|e| format!("Failed to archive {}: {}", path, e))
+    };
+
+    let manifest = VaultManifest {
+        version: FORMAT_VERSION,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        accounts: accounts.clone(),
+    };
+    let manifest = serde_json::to_vec_pretty(&manifest)
+        .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    add("manifest.json".to_string(), &manifest)?;
+
+    let usage = read_usage_cache();
+    for name in &accounts {
+        for (rel, bytes) in account_files(name)? {
+            add(format!("accounts/{}/{}", name, rel), &bytes)?;
+        }
+        if let Some(entry) = usage.get(name) {
+            let bytes = serde_json::to_vec(entry)
+                .map_err(|e| format!("Failed to serialize usage for '{}': {}", name, e))?;
+            add(format!("usage/{}.json", name), &bytes)?;
+        }
+    }
+
+    let selected: BTreeSet<&str> = accounts.iter().map(String::as_str).collect();
+    let mut history = String::new();
+    for sample in read_samples() {
+        if selected.contains(sample.account.as_str()) {
+            if let Ok(line) = serde_json::to_string(&sample) {
+                history.push_str(&line);
+                history.push('\n');
+            }
+        }
+    }
+    add("history.jsonl".to_string(), history.as_bytes())?;
+
+    let archive = zip
+        .finish()
+        .map_err(|e| format!("Failed to finish archive: {}", e))?
+        .into_inner();
+    write_private(
+        std::path::Path::new(&dest),
+        &encrypt(&passphrase, &archive)?,
+    )?;
+    Ok(accounts)
+}
+
+/// The decrypted, validated contents of a vault archive.
+#[derive(Default)]
+struct VaultContents {
+    accounts: BTreeMap<String, BTreeMap<String, Vec<u8>>>,
+    usage: BTreeMap<String, serde_json::Value>,
+    history: Vec<UsageSample>,
+}
+
+/// Reads every entry, refusing the archive on anything unexpected.
+fn read_contents(zip_bytes: Vec<u8>) -> Result<VaultContents, String> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(zip_bytes))
+        .map_err(|e| format!("Vault archive is damaged: {}", e))?;
+
+    let mut written = 0u64;
+    let mut raw = BTreeMap::new();
+    for i in 0..archive.len() {
+        let mut entry = archive
+            .by_index(i)
+            .map_err(|e| format!("Failed to read archive entry {}: {}", i, e))?;
+        if entry.is_dir() {
+            continue;
+        }
+        let name = entry.name().to_string();
+        if escapes_root(&name) {
+            return Err(format!("'{}' would extract outside the vault", name));
+        }
+        let mut bytes = Vec::new();
+        copy_bounded(
+            &mut entry,
+            &mut bytes,
+            &name,
+            &mut written,
+            MAX_UNCOMPRESSED_BYTES,
+        )?;
+        raw.insert(name, bytes);
+    }
+
+    let manifest: VaultManifest = raw
+        .remove("manifest.json")
+        .ok_or("Archive has no manifest.json")
+        .and_then(|bytes| {
+            serde_json::from_slice(&bytes).map_err(|_| "Archive manifest is invalid")
+        })?;
+    if manifest.version != FORMAT_VERSION {
+        return Err(format!(
+            "Unsupported vault archive version {}",
+            manifest.version
+        ));
+    }
+    for name in &manifest.accounts {
+        validate_account_name(name)?;
+    }
+    let listed: BTreeSet<&str> = manifest.accounts.iter().map(String::as_str).collect();
+
+    let mut contents = VaultContents::default();
+    for (path, bytes) in raw {
+        if path == "history.jsonl" {
+            contents.history = String::from_utf8_lossy(&bytes)
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect();
+        } else if let Some(file) = path.strip_prefix("usage/") {
+            let name = file.strip_suffix(".json").unwrap_or(file);
+            if !listed.contains(name) {
+                return Err(format!("'{}' belongs to an unlisted account", path));
+            }
+            let value = serde_json::from_slice(&bytes)
+                .map_err(|e| format!("'{}' is not valid JSON: {}", path, e))?;
+            contents.usage.insert(name.to_string(), value);
+        } else if let Some((name, rel)) = path
+            .strip_prefix("accounts/")
+            .and_then(|p| p.split_once('/'))
+        {
+            if !listed.contains(name) || rel.is_empty() {
+                return Err(format!("'{}' belongs to an unlisted account", path));
+            }
+            contents
+                .accounts
+                .entry(name.to_string())
+                .or_default()
+                .insert(rel.to_string(), bytes);
+        } else {
+            return Err(format!("Unexpected entry '{}' in archive", path));
+        }
+    }
+    if let Some(missing) = manifest
+        .accounts
+        .iter()
+        .find(|name| !contents.accounts.contains_key(*name))
+    {
+        return Err(format!("Archive has no files for account '{}'", missing));
+    }
+    Ok(contents)
+}
+
+/// The local name an archived account lands under, or `None` to skip it.
+fn target_name(name: &str, policy: ConflictPolicy) -> Result<Option<String>, String> {
+    if !account_dir(name).exists() {
+        return Ok(Some(name.to_string()));
+    }
+    match policy {
+        ConflictPolicy::Skip => Ok(None),
+        ConflictPolicy::Overwrite => Ok(Some(name.to_string())),
+        ConflictPolicy::Rename => {
+            let renamed = (2..)
+                .map(|n| format!("{}-{}", name, n))
+                .find(|candidate| !account_dir(candidate).exists())
+                .unwrap_or_default();
+            validate_account_name(&renamed)?;
+            Ok(Some(renamed))
+        }
+    }
+}
+
+/// Writes an imported account. The files are staged next to the vault's
+/// accounts and swapped in whole, so a failed write never loses an account
+/// being overwritten; its keychain secret is only dropped once the swap
+/// succeeds, and the live credentials follow if it is the active account.
+fn write_account(name: &str, files: &BTreeMap<String, Vec<u8>>) -> Result<(), String> {
+    let dir = account_dir(name);
+    let had_secret = dir.join(KEYCHAIN_MARKER).exists();
+    let staging = accounts_dir().join(format!(".import-{}", name));
+    let _ = fs::remove_dir_all(&staging);
+    let stage = || -> Result<(), String> {
+        for (rel, bytes) in files {
+            write_private(&staging.join(rel), bytes)?;
+        }
+        replace_dir(&staging, &dir).map_err(|e| format!("Failed to replace '{}': {}", name, e))
+    };
+    stage().inspect_err(|_| {
+        let _ = fs::remove_dir_all(&staging);
+    })?;
+    if had_secret {
+        secrets::forget(name);
+    }
+    if load_settings().keychain_secrets {
+        if let Err(e) = secrets::migrate_account(name) {
+            log::warn!("Failed to move '{}' into the keychain: {}", name, e);
+        }
+    }
+    if load_state().active_account.as_deref() == Some(name) {
+        apply_credentials(name)?;
+    }
+    Ok(())
+}
+
+fn merge_usage(
+    imported: &BTreeMap<String, String>,
+    usage: BTreeMap<String, serde_json::Value>,
+) -> Result<(), String> {
+    let mut cache = read_usage_cache();
+    for (name, entry) in usage {
+        if let Some(target) = imported.get(&name) {
+            cache.insert(target.clone(), entry);
+        }
+    }
+    let body = serde_json::to_vec_pretty(&cache)
+        .map_err(|e| format!("Failed to serialize usage cache: {}", e))?;
+    write_private(&codex_root().join("usage_cache.json"), &body)
+}
+
+fn merge_history(
+    imported: &BTreeMap<String, String>,
+    history: Vec<UsageSample>,
+) -> Result<(), String> {
+    let mut lines = String::new();
+    for mut sample in history {
+        let Some(target) = imported.get(&sample.account) else {
+            continue;
+        };
+        sample.account = target.clone();
+        if let Ok(line) = serde_json::to_string(&sample) {
+            lines.push_str(&line);
+            lines.push('\n');
+        }
+    }
+    if lines.is_empty() {
+        return Ok(());
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(history_path())
+        .map_err(|e| format!("Failed to open usage history: {}", e))?;
+    file.write_all(lines.as_bytes())
+        .map_err(|e| format!("Failed to append usage history: {}", e))
+}
+
+/// Decrypts and validates the archive at `src`, then imports its accounts,
+/// resolving name clashes per `on_conflict`. Nothing is written unless the
+/// whole archive validates.
+#[tauri::command]
+pub fn import_vault(
+    app: AppHandle,
+    src: String,
+    passphrase: String,
+    on_conflict: ConflictPolicy,
+) -> Result<VaultImport, String> {
+    let data = fs::read(&src).map_err(|e| format!("Failed to read '{}': {}", src, e))?;
+    let contents = read_contents(decrypt(&passphrase, &data)?)?;
+
+    with_watcher_paused(&app, || {
+        let mut report = VaultImport::default();
+        for (name, files) in &contents.accounts {
+            match target_name(name, on_conflict)? {
+                Some(target) => {
+                    write_account(&target, files)?;
+                    report.imported.insert(name.clone(), target);
+                }
+                None => report.skipped.push(name.clone()),
+            }
+        }
+        merge_usage(&report.imported, contents.usage)?;
+        merge_history(&report.imported, contents.history)?;
+        Ok(report)
+    })
+}
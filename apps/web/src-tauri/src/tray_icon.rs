@@ -0,0 +1,64 @@
+//! Tray icons rendered from the active account's usage.
+//!
+//! A progress ring around the edge fills clockwise from 12 o'clock with the
+//! 5h usage. On macOS the ring is drawn as a monochrome template image so
+//! the menubar recolors it for light and dark mode; elsewhere it is painted
+//! in the usage color over the app icon.
+
+use std::f64::consts::TAU;
+use tauri::image::Image;
+
+/// Ring thickness as a fraction of the icon's size.
+const RING_WIDTH: f64 = 0.14;
+
+/// Alpha of the unfilled part of the ring.
+const TRACK_ALPHA: u8 = 0x50;
+
+fn paint_ring(
+    rgba: &mut [u8],
+    width: u32,
+    height: u32,
+    percent: f64,
+    fill: [u8; 3],
+    track: [u8; 3],
+) {
+    let size = width.min(height) as f64;
+    let outer = size / 2.0;
+    let inner = outer - size * RING_WIDTH;
+    let (cx, cy) = (width as f64 / 2.0, height as f64 / 2.0);
+    let filled = (percent / 100.0).clamp(0.0, 1.0);
+
+    for y in 0..height {
+        for x in 0..width {
+            let (dx, dy) = (x as f64 + 0.5 - cx, y as f64 + 0.5 - cy);
+            let distance = (dx * dx + dy * dy).sqrt();
+            if distance < inner || distance > outer {
+                continue;
+            }
+            // Clockwise angle from 12 o'clock, as a fraction of a full turn.
+            let turn = dx.atan2(-dy).rem_euclid(TAU) / TAU;
+            let pixel = if turn <= filled {
+                [fill[0], fill[1], fill[2], 0xff]
+            } else {
+                [track[0], track[1], track[2], TRACK_ALPHA]
+            };
+            let i = ((y * width + x) * 4) as usize;
+            rgba[i..i + 4].copy_from_slice(&pixel);
+        }
+    }
+}
+
+/// `base` with a usage ring in `rgb` painted over its edge.
+pub fn colored_ring(base: &Image<'_>, percent: f64, rgb: [u8; 3]) -> Image<'static> {
+    let (width, height) = (base.width(), base.height());
+    let mut rgba = base.rgba().to_vec();
+    paint_ring(&mut rgba, width, height, percent, rgb, [0x80, 0x80, 0x80]);
+    Image::new_owned(rgba, width, height)
+}
+
+/// A black-on-transparent usage ring for use as a macOS template icon.
+pub fn template_ring(size: u32, percent: f64) -> Image<'static> {
+    let mut rgba = vec![0; (size * size * 4) as usize];
+    paint_ring(&mut rgba, size, size, percent, [0, 0, 0], [0, 0, 0]);
+    Image::new_owned(rgba, size, size)
+}
@@ -0,0 +1,267 @@
+use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
+use std::mem::size_of;
+use tauri::AppHandle;
+
+use crate::{codex_root, current_state, CacheEntry};
+
+/// Once raw blobs across all entries exceed this, non-active accounts drop
+/// theirs. The typed fields (and so every percentage) are always kept.
+pub const RAW_USAGE_CAP_BYTES: usize = 512 * 1024;
+
+/// Non-numeric values read as missing instead of failing the whole cache.
+fn lenient_f64<'de, D: Deserializer<'de>>(d: D) -> Result<Option<f64>, D::Error> {
+    Ok(serde_json::Value::deserialize(d)?.as_f64())
+}
+
+/// Unix seconds from a number (seconds, or milliseconds when implausibly
+/// large) or an RFC 3339 string; anything else reads as missing.
+fn lenient_timestamp<'de, D: Deserializer<'de>>(d: D) -> Result<Option<f64>, D::Error> {
+    Ok(match serde_json::Value::deserialize(d)? {
+        serde_json::Value::Number(n) => n.as_f64().map(|ts| {
+            if ts > MILLIS_THRESHOLD {
+                ts / 1000.0
+            } else {
+                ts
+            }
+        }),
+        serde_json::Value::String(s) => chrono::DateTime::parse_from_rfc3339(&s)
+            .ok()
+            .map(|dt| dt.timestamp() as f64),
+        _ => None,
+    })
+}
+
+/// Timestamps above this are taken to be milliseconds (it is year 33658 in seconds).
+const MILLIS_THRESHOLD: f64 = 1e12;
+
+fn lenient_window<'de, D: Deserializer<'de>>(d: D) -> Result<Option<UsageWindow>, D::Error> {
+    Ok(serde_json::from_value(serde_json::Value::deserialize(d)?).ok())
+}
+
+/// One rate-limit window; only the fields the app reads are kept.
+#[derive(Deserialize, Clone, Default)]
+pub struct UsageWindow {
+    #[serde(default, deserialize_with = "lenient_f64")]
+    pub used: Option<f64>,
+    #[serde(default, deserialize_with = "lenient_f64")]
+    pub limit: Option<f64>,
+    /// Unix seconds the window starts over.
+    #[serde(default, deserialize_with = "lenient_timestamp")]
+    pub resets_at: Option<f64>,
+    /// Seconds until the reset, counted from the entry's `updated_at`; used
+    /// when the CLI reports no absolute `resets_at`.
+    #[serde(default, deserialize_with = "lenient_f64")]
+    pub resets_in: Option<f64>,
+}
+
+impl UsageWindow {
+    /// Usage as a percentage of the limit; a missing limit counts as 1.
+    pub fn percent(&self) -> f64 {
+        (self.used.unwrap_or(0.0) / self.limit.unwrap_or(1.0)) * 100.0
+    }
+
+    /// Quota left in the window; `None` when the limit isn't known.
+    pub fn remaining(&self) -> Option<f64> {
+        Some((self.limit? - self.used.unwrap_or(0.0)).max(0.0))
+    }
+
+    /// `resets_at` as whole unix seconds.
+    pub fn reset_time(&self) -> Option<i64> {
+        self.resets_at.map(|ts| ts as i64)
+    }
+}
+
+#[derive(Deserialize, Clone, Default)]
+pub struct UsageLimits {
+    #[serde(default, deserialize_with = "lenient_window")]
+    pub limit_5h: Option<UsageWindow>,
+    #[serde(default, deserialize_with = "lenient_window")]
+    pub limit_weekly: Option<UsageWindow>,
+}
+
+/// When the CLI wrote this entry: the numeric `timestamp` if present, else the
+/// RFC 3339 `updated_at`. `None` means the age is unknown.
+fn parse_updated_at(raw: &serde_json::Value) -> Option<i64> {
+    raw["timestamp"].as_f64().map(|ts| ts as i64).or_else(|| {
+        raw["updated_at"]
+            .as_str()
+            .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+            .map(|dt| dt.timestamp())
+    })
+}
+
+impl CacheEntry {
+    /// Builds the compact entry, keeping the original blob until the cap evicts it.
+    pub fn from_raw(raw: serde_json::Value) -> Self {
+        let mut limits: UsageLimits =
+            serde_json::from_value(raw["limits"].clone()).unwrap_or_default();
+        let updated_at = parse_updated_at(&raw);
+        if let Some(updated_at) = updated_at {
+            for window in [&mut limits.limit_5h, &mut limits.limit_weekly]
+                .into_iter()
+                .flatten()
+            {
+                if window.resets_at.is_none() {
+                    window.resets_at = window.resets_in.map(|secs| updated_at as f64 + secs);
+                }
+            }
+        }
+        CacheEntry {
+            limits,
+            updated_at,
+            raw: Some(raw),
+        }
+    }
+
+    /// When the window that limits this account most starts over, if that is
+    /// after `now`: the fuller window first, the other as a fallback.
+    pub fn next_reset(&self, now: i64) -> Option<i64> {
+        let mut windows: Vec<&UsageWindow> = [&self.limits.limit_5h, &self.limits.limit_weekly]
+            .into_iter()
+            .flatten()
+            .collect();
+        windows.sort_by(|a, b| b.percent().total_cmp(&a.percent()));
+        windows
+            .into_iter()
+            .filter_map(UsageWindow::reset_time)
+            .find(|&at| at > now)
+    }
+
+    /// Older than `max_age_secs` at `now`. Entries of unknown age are never stale.
+    pub fn is_stale(&self, now: i64, max_age_secs: i64) -> bool {
+        self.updated_at
+            .is_some_and(|updated_at| now - updated_at > max_age_secs)
+    }
+}
+
+fn raw_size(entry: &CacheEntry) -> usize {
+    entry
+        .raw
+        .as_ref()
+        .and_then(|raw| serde_json::to_string(raw).ok())
+        .map_or(0, |s| s.len())
+}
+
+/// Drops raw blobs for non-active accounts once their total passes the cap.
+pub fn enforce_raw_cap(cache: &mut HashMap<String, CacheEntry>, active: Option<&str>) {
+    let total: usize = cache.values().map(raw_size).sum();
+    if total <= RAW_USAGE_CAP_BYTES {
+        return;
+    }
+    for (name, entry) in cache.iter_mut() {
+        if Some(name.as_str()) != active {
+            entry.raw = None;
+        }
+    }
+}
+
+/// The usage cache entry for `name` exactly as the CLI wrote it: the kept
+/// blob, or straight from `usage_cache.json` once the cap has evicted it.
+/// `None` when the account has no entry.
+#[tauri::command]
+pub fn get_raw_usage(app: AppHandle, name: String) -> Result<Option<serde_json::Value>, String> {
+    if let Some(raw) = current_state(&app)
+        .usage_cache
+        .get(&name)
+        .and_then(|entry| entry.raw.clone())
+    {
+        return Ok(Some(raw));
+    }
+    let path = codex_root().join("usage_cache.json");
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.to_string_lossy(), e)),
+    };
+    let mut parsed: HashMap<String, serde_json::Value> = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse {}: {}", path.to_string_lossy(), e))?;
+    Ok(parsed.remove(&name))
+}
+
+#[derive(serde::Serialize)]
+pub struct UsageMemory {
+    entries: usize,
+    raw_entries: usize,
+    typed_bytes: usize,
+    raw_bytes: usize,
+    total_bytes: usize,
+    cap_bytes: usize,
+}
+
+/// Approximate bytes held by the usage cache: struct sizes plus key lengths
+/// for the typed data, serialized length for the raw blobs.
+#[tauri::command]
+pub fn cache_memory_estimate(app: AppHandle) -> UsageMemory {
+    let cache = current_state(&app).usage_cache;
+    let typed_bytes = cache
+        .keys()
+        .map(|k| k.len() + size_of::<String>() + size_of::<CacheEntry>())
+        .sum::<usize>()
+        + cache
+            .values()
+            .map(|e| {
+                [&e.limits.limit_5h, &e.limits.limit_weekly]
+                    .iter()
+                    .filter(|w| w.is_some())
+                    .count()
+                    * size_of::<UsageWindow>()
+            })
+            .sum::<usize>();
+    let raw_bytes = cache.values().map(raw_size).sum();
+
+    UsageMemory {
+        entries: cache.len(),
+        raw_entries: cache.values().filter(|e| e.raw.is_some()).count(),
+        typed_bytes,
+        raw_bytes,
+        total_bytes: typed_bytes + raw_bytes,
+        cap_bytes: RAW_USAGE_CAP_BYTES,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(bytes: usize) -> CacheEntry {
+        CacheEntry::from_raw(serde_json::json!({ "padding": "x".repeat(bytes) }))
+    }
+
+    #[test]
+    fn cap_keeps_the_active_blob() {
+        let mut cache = HashMap::from([
+            ("work".to_string(), entry(RAW_USAGE_CAP_BYTES / 2)),
+            ("home".to_string(), entry(RAW_USAGE_CAP_BYTES / 2)),
+        ]);
+        enforce_raw_cap(&mut cache, Some("work"));
+        assert!(cache["work"].raw.is_some());
+        assert!(cache["home"].raw.is_none());
+    }
+
+    #[test]
+    fn under_the_cap_nothing_is_evicted() {
+        let mut cache = HashMap::from([
+            ("work".to_string(), entry(16)),
+            ("home".to_string(), entry(16)),
+        ]);
+        enforce_raw_cap(&mut cache, None);
+        assert!(cache.values().all(|e| e.raw.is_some()));
+    }
+
+    #[test]
+    fn fresh_stale_and_undated_entries() {
+        const NOW: i64 = 1_700_000_000;
+        const HOUR: i64 = 3600;
+        let fresh = CacheEntry::from_raw(serde_json::json!({ "timestamp": NOW - 60 }));
+        let stale =
+            CacheEntry::from_raw(serde_json::json!({ "updated_at": "2023-11-14T19:13:20Z" }));
+        let undated = CacheEntry::from_raw(serde_json::json!({ "updated_at": "yesterday" }));
+
+        assert!(!fresh.is_stale(NOW, HOUR));
+        assert_eq!(stale.updated_at, Some(NOW - 3 * HOUR));
+        assert!(stale.is_stale(NOW, HOUR));
+        assert_eq!(undated.updated_at, None);
+        assert!(!undated.is_stale(NOW, HOUR));
+    }
+}